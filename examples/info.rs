@@ -11,8 +11,8 @@ use seify_bladerf::board::bladerf1::BladeRf1;
 // use std::time::Duration;
 // use seify_bladerf::board::bladerf1::{BLADERF1_USB_PID, BLADERF1_USB_VID};
 // use seify_bladerf::nios::constants::{NIOS_PKT_8X32_TARGET_CONTROL, NIOS_PKT_FLAG_READ};
-// use seify_bladerf::nios::packet8x32::NiosPacket8x32;
-// use seify_bladerf::nios::packet8x8::NiosPacket8x8;
+// use seify_bladerf::nios::packet::NiosPacket8x32;
+// use seify_bladerf::nios::packet::NiosPacket8x8;
 
 fn main() -> Result<()> {
     env_logger::init();
@@ -43,7 +43,7 @@ pub struct BladerfLoopbackModes {
 /**
 * Loopback options
 */
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum BladerfLoopback {
     /** Disables loopback and returns to normal operation. */
     BladerfLbNone = 0,
@@ -85,6 +85,132 @@ pub enum BladerfLoopback {
     BladerfLbRficBist,
 }
 
+/**
+ * RX Mux modes
+ *
+ * These values describe the source of samples to the RX FIFOs in the FPGA.
+ * They map directly to rx_mux_mode_t inside the FPGA's source code.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RxMux {
+    /** Read baseband samples from the LMS6002D. This is the default mode of
+     *  operation. */
+    RxMuxBaseband = 0x0,
+
+    /** Read samples from 12 bit counters.
+     *
+     * The I channel counts up while the Q channel counts down.
+     */
+    RxMux12bitCounter = 0x1,
+
+    /** Read samples from a 32 bit up-counter.
+     *
+     * I and Q form a little-endian value.
+     */
+    RxMux32bitCounter = 0x2,
+
+    /* RX_MUX setting 0x3 is reserved for future use */
+    /** Read samples from the baseband TX input to the FPGA (from the host) */
+    RxMuxDigitalLoopback = 0x4,
+}
+
+/**
+ * Trigger role
+ *
+ * Each device in a chain of triggered devices is either the master, which
+ * fires the trigger signal, or a slave, which only observes it.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfTriggerRole {
+    /** Triggering functionality is disabled */
+    BladerfTriggerRoleDisabled,
+
+    /** This device is the trigger master. Its trigger signal will be output
+     *  and this device will determine when all devices shall trigger. */
+    BladerfTriggerRoleMaster,
+
+    /** This device is the trigger slave. This device's trigger signal will
+     *  be input and this devices will wait for the master's trigger signal
+     *  assertion. */
+    BladerfTriggerRoleSlave,
+}
+
+/**
+ * Trigger signal selection
+ *
+ * The signal used to synchronize all devices in a trigger chain.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfTriggerSignal {
+    /** J71 pin 4, mini_exp_1 on bladeRF 1 */
+    BladerfTriggerJ71_4,
+    /** J51 pin 1, mini_exp_1 on bladeRF 2.0 */
+    BladerfTriggerJ51_1,
+    /** mini_exp_1, hardware-independent */
+    BladerfTriggerMiniExp1,
+}
+
+/**
+ * Trigger configuration
+ *
+ * Created with `BladeRf1::trigger_init` and used for all subsequent trigger
+ * operations.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BladerfTrigger {
+    /** RX/TX channel associated with trigger */
+    pub channel: u8,
+    /** Role of the device in a trigger chain */
+    pub role: BladerfTriggerRole,
+    /** Pin or signal being used */
+    pub signal: BladerfTriggerSignal,
+    /** Reserved field for future options. This is unused and should be set
+     *  to 0. */
+    pub options: u64,
+}
+
+/**
+ * Trigger state
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BladerfTriggerState {
+    /** Trigger is armed */
+    pub is_armed: bool,
+    /** The trigger signal has been asserted */
+    pub has_fired: bool,
+    /** A fire request is pending (master only) */
+    pub fire_requested: bool,
+}
+
+/**
+ * Low-Pass Filter (LPF) mode
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfLpfMode {
+    /**< LPF connected and enabled */
+    BladerfLpfNormal,
+    /**< LPF bypassed */
+    BladerfLpfBypassed,
+    /**< LPF disabled */
+    BladerfLpfDisabled,
+}
+
+/**
+ * SMB clock port mode of operation
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfSmbMode {
+    /**< Not in use. Device operates from its onboard clock and does not use
+     *   the SMB port. */
+    BladerfSmbModeDisabled,
+    /**< Device outputs a 38.4 MHz reference clock on the SMB port, which may
+     *   be used to drive another device's reference input. */
+    BladerfSmbModeOutput,
+    /**< Device configures the SMB port as a reference clock input, taking
+     *   its 38.4 MHz reference from another device. */
+    BladerfSmbModeInput,
+}
+
 /**
  * Gain control modes
  *
@@ -135,6 +261,228 @@ pub enum BladerfGainMode {
     BladerfGainHybridAgc,
 }
 
+/**
+ * Full scale of the raw FPGA IQ correction values. Both gain and phase
+ * accept values in [-4096, 4096].
+ */
+pub const BLADERF_IQ_CORR_MAX: i16 = 4096;
+
+/**
+ * Phase correction, in degrees, corresponding to a raw value of
+ * BLADERF_IQ_CORR_MAX.
+ */
+pub const BLADERF_IQ_CORR_PHASE_MAX_DEG: f64 = 10.0;
+
+/**
+ * FPGA IQ imbalance correction
+ *
+ * Both values are applied to the Q branch, relative to I.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IqCorrection {
+    /** Gain correction. [-4096, 4096] is scaled to [-1.0, 1.0] and applied as (1.0 + gain) */
+    pub gain: i16,
+    /** Phase correction. [-4096, 4096] is scaled to [-10, 10] degrees */
+    pub phase: i16,
+}
+
+impl IqCorrection {
+    /// Build a correction from a Q/I gain ratio in dB and a phase offset in degrees.
+    /// Values outside of the representable range are clamped.
+    pub fn from_db_deg(gain_db: f64, phase_deg: f64) -> Self {
+        let full_scale = BLADERF_IQ_CORR_MAX as f64;
+
+        let gain = (10f64.powf(gain_db / 20.0) - 1.0) * full_scale;
+        let phase = phase_deg / BLADERF_IQ_CORR_PHASE_MAX_DEG * full_scale;
+
+        Self {
+            gain: gain.round().clamp(-full_scale, full_scale) as i16,
+            phase: phase.round().clamp(-full_scale, full_scale) as i16,
+        }
+    }
+
+    /// Q/I gain ratio in dB. A raw gain of -4096 yields negative infinity.
+    pub fn gain_db(&self) -> f64 {
+        20.0 * (1.0 + self.gain as f64 / BLADERF_IQ_CORR_MAX as f64).log10()
+    }
+
+    /// Phase offset of Q relative to I in degrees.
+    pub fn phase_deg(&self) -> f64 {
+        self.phase as f64 * BLADERF_IQ_CORR_PHASE_MAX_DEG / BLADERF_IQ_CORR_MAX as f64
+    }
+}
+
+/**
+ * Version structure for FPGA, firmware, libbladeRF, and associated utilities
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BladerfVersion {
+    /**< Major version */
+    pub major: u16,
+    /**< Minor version */
+    pub minor: u16,
+    /**< Patch version */
+    pub patch: u16,
+}
+
+impl BladerfVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl std::fmt::Display for BladerfVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/**
+ * USB bus speed the device is connected at
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfDevSpeed {
+    /**< Unknown or unsupported speed */
+    BladerfDeviceSpeedUnknown,
+    /**< USB 2.0 High Speed */
+    BladerfDeviceSpeedHigh,
+    /**< USB 3.0 SuperSpeed */
+    BladerfDeviceSpeedSuper,
+}
+
+/**
+ * Stream direction
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfDirection {
+    /**< Receive direction */
+    BladerfRx = 0,
+    /**< Transmit direction */
+    BladerfTx = 1,
+}
+
+/**
+ * Sample format
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfFormat {
+    /**
+     * Signed, Complex 16-bit Q11. This is the native format of the DAC data.
+     *
+     * Values in the range [-2048, 2048) are used to represent [-1.0, 1.0).
+     * Samples are interleaved IQ pairs, with I first.
+     */
+    BladerfFormatSc16Q11,
+
+    /**
+     * This format is the same as the ::BladerfFormatSc16Q11 format, except
+     * the first 4 samples in every <i>block*</i> of samples are replaced with
+     * metadata organized as follows. All fields are little-endian byte order.
+     *
+     * <pre>
+     *  .-------------.------------.----------------------------------.
+     *  |   Bytes     |  Length    | Description                      |
+     *  +-------------+------------+----------------------------------+
+     *  | 0x00 - 0x03 |  4 bytes   | Reserved                         |
+     *  | 0x04 - 0x0b |  8 bytes   | 64-bit timestamp                 |
+     *  | 0x0c - 0x0f |  4 bytes   | Flags                            |
+     *  '-------------'------------'----------------------------------'
+     * </pre>
+     *
+     * *The number of samples in a <i>block</i> is dependent upon the USB
+     * speed being used: 256 samples for USB 2.0 and 512 samples for USB 3.0.
+     */
+    BladerfFormatSc16Q11Meta,
+}
+
+/**
+ * Mark the associated buffer as the start of a burst transmission.
+ *
+ * When using this flag, the timestamp of the metadata must be set to the
+ * sample time the burst should start at.
+ */
+pub const BLADERF_META_FLAG_TX_BURST_START: u32 = 1 << 0;
+
+/**
+ * Mark the associated buffer as the end of a burst transmission. This will
+ * flush the remainder of the sync interface's current working buffer and
+ * enqueue samples into the hardware's transmit FIFO.
+ *
+ * Zeros are padded after the end of the burst, so the hardware transmits
+ * silence until the next burst.
+ */
+pub const BLADERF_META_FLAG_TX_BURST_END: u32 = 1 << 1;
+
+/**
+ * Use this flag in conjunction with ::BLADERF_META_FLAG_TX_BURST_START to
+ * indicate that the burst should be transmitted as soon as possible, as
+ * opposed to waiting for a specific timestamp.
+ */
+pub const BLADERF_META_FLAG_TX_NOW: u32 = 1 << 2;
+
+/**
+ * Use this flag within a burst to indicate that the timestamp of the
+ * following samples should be updated. The remainder of the current block
+ * is zero-padded, and the hardware transmits zeros until the new timestamp.
+ */
+pub const BLADERF_META_FLAG_TX_UPDATE_TIMESTAMP: u32 = 1 << 3;
+
+/**
+ * This flag indicates that calls to the RX streamer should return samples
+ * as soon as possible, rather than waiting for the specified timestamp.
+ */
+pub const BLADERF_META_FLAG_RX_NOW: u32 = 1 << 31;
+
+/**
+ * A sample overrun has occurred. This indicates that either the host
+ * (more likely) or the FPGA is not keeping up with the incoming samples.
+ */
+pub const BLADERF_META_STATUS_OVERRUN: u32 = 1 << 0;
+
+/**
+ * A sample underrun has occurred. This generally only occurs on the TX
+ * channel when the FPGA is starved of samples.
+ */
+pub const BLADERF_META_STATUS_UNDERRUN: u32 = 1 << 1;
+
+/**
+ * Sample metadata
+ *
+ * This structure is used in conjunction with the ::BladerfFormatSc16Q11Meta
+ * format to TX scheduled bursts or retrieve timestamp information about
+ * received samples.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BladerfMetadata {
+    /**
+     * Free-running FPGA counter that monotonically increases at the sample
+     * rate of the associated channel.
+     */
+    pub timestamp: u64,
+
+    /**
+     * Input bit field to control the behavior of the call that the metadata
+     * structure is passed to. See the BLADERF_META_FLAG_* constants.
+     */
+    pub flags: u32,
+
+    /**
+     * Output bit field to denote the status of transmissions/receptions.
+     * See the BLADERF_META_STATUS_* constants.
+     */
+    pub status: u32,
+
+    /**
+     * This output parameter is updated to reflect the actual number of
+     * contiguous samples that have been populated in an RX buffer.
+     */
+    pub actual_count: usize,
+}
+
 #[allow(dead_code)]
 pub(crate) const BLADERF_MODULE_RX: u8 = bladerf_channel_rx!(0);
 #[allow(dead_code)]
@@ -144,14 +492,222 @@ pub trait BladeRf {
     //fn nios_send(&self, endpoint_in: u8, endpoint_out: u8, pkt: Vec<u8>) -> Result<Vec<u8>>;
 }
 
-#[derive(Clone, Default)]
-pub(crate) struct BladerfRationalRate {
+/**
+ * Rational sample rate representation
+ *
+ * Sample rates are in the form of integer + num/denom.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct BladerfRationalRate {
     /* Integer portion */
-    pub(crate) integer: u64,
+    pub integer: u64,
     /* Numerator in fractional portion */
-    pub(crate) num: u64,
+    pub num: u64,
     /* Denominator in fractional portion. This must be greater than 0. */
-    pub(crate) den: u64,
+    pub den: u64,
+}
+
+impl BladerfRationalRate {
+    pub const fn new(integer: u64, num: u64, den: u64) -> Self {
+        Self { integer, num, den }
+    }
+
+    /// Rate with no fractional portion.
+    pub const fn from_integer(integer: u64) -> Self {
+        Self::new(integer, 0, 1)
+    }
+
+    /// Approximate `value` as a rational rate whose fractional portion has denominator `den`.
+    pub fn from_f64(value: f64, den: u64) -> Self {
+        let integer = value.trunc();
+        let num = ((value - integer) * den as f64).round() as u64;
+        let mut rate = Self::new(integer as u64, num, den);
+        rate.reduce();
+        rate
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        if self.den == 0 {
+            return self.integer as f64;
+        }
+        self.integer as f64 + self.num as f64 / self.den as f64
+    }
+
+    /// Move whole units out of the fraction and reduce it to lowest terms.
+    pub fn reduce(&mut self) {
+        if self.den > 0 && self.num >= self.den {
+            self.integer += self.num / self.den;
+            self.num %= self.den;
+        }
+
+        let val = gcd(self.num as u128, self.den as u128) as u64;
+        if let (Some(num), Some(den)) = (self.num.checked_div(val), self.den.checked_div(val)) {
+            self.num = num;
+            self.den = den;
+        }
+    }
+
+    /* Represent as a single improper fraction */
+    fn to_fraction(&self) -> (u128, u128) {
+        let den = self.den.max(1) as u128;
+        (self.integer as u128 * den + self.num as u128, den)
+    }
+
+    /* Reduce an improper fraction, or None if it is not representable */
+    fn from_fraction(num: u128, den: u128) -> Option<Self> {
+        let val = gcd(num, den).max(1);
+        let (num, den) = (num / val, den / val);
+        Some(Self::new(
+            u64::try_from(num / den).ok()?,
+            (num % den) as u64,
+            u64::try_from(den).ok()?,
+        ))
+    }
+
+    /// `self + rhs`, or `None` if the result overflows.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        let (a_num, a_den) = self.to_fraction();
+        let (b_num, b_den) = rhs.to_fraction();
+        let num = a_num
+            .checked_mul(b_den)?
+            .checked_add(b_num.checked_mul(a_den)?)?;
+        Self::from_fraction(num, a_den.checked_mul(b_den)?)
+    }
+
+    /// `self - rhs`, or `None` if `rhs` is greater than `self`.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        let (a_num, a_den) = self.to_fraction();
+        let (b_num, b_den) = rhs.to_fraction();
+        let num = a_num
+            .checked_mul(b_den)?
+            .checked_sub(b_num.checked_mul(a_den)?)?;
+        Self::from_fraction(num, a_den.checked_mul(b_den)?)
+    }
+
+    /// `self * rhs`, or `None` if the result overflows.
+    pub fn checked_mul(self, rhs: u64) -> Option<Self> {
+        let (num, den) = self.to_fraction();
+        Self::from_fraction(num.checked_mul(rhs as u128)?, den)
+    }
+
+    /// `self / rhs`, or `None` if `rhs` is zero or the result overflows.
+    pub fn checked_div(self, rhs: u64) -> Option<Self> {
+        if rhs == 0 {
+            return None;
+        }
+        let (num, den) = self.to_fraction();
+        Self::from_fraction(num, den.checked_mul(rhs as u128)?)
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let t = b;
+        b = a % t;
+        a = t;
+    }
+    a
+}
+
+/// Panics if the result overflows.
+impl std::ops::Add for BladerfRationalRate {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.checked_add(rhs)
+            .expect("attempt to add rational rates with overflow")
+    }
+}
+
+/// Panics if `rhs` is greater than `self`, as rates cannot be negative. Use
+/// [`BladerfRationalRate::checked_sub`] if that is not known in advance.
+impl std::ops::Sub for BladerfRationalRate {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs)
+            .expect("attempt to subtract rational rates with overflow")
+    }
+}
+
+/// Panics if the result overflows.
+impl std::ops::Mul<u64> for BladerfRationalRate {
+    type Output = Self;
+
+    fn mul(self, rhs: u64) -> Self {
+        self.checked_mul(rhs)
+            .expect("attempt to multiply rational rate with overflow")
+    }
+}
+
+/// Panics if `rhs` is zero or the result overflows.
+impl std::ops::Div<u64> for BladerfRationalRate {
+    type Output = Self;
+
+    fn div(self, rhs: u64) -> Self {
+        assert!(rhs != 0, "attempt to divide rational rate by zero");
+        self.checked_div(rhs)
+            .expect("attempt to divide rational rate with overflow")
+    }
+}
+
+impl PartialEq for BladerfRationalRate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for BladerfRationalRate {}
+
+impl PartialOrd for BladerfRationalRate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BladerfRationalRate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        /* Compare whole parts first, so the cross products of the remaining
+         * proper fractions cannot overflow */
+        let (a_num, a_den) = self.to_fraction();
+        let (b_num, b_den) = other.to_fraction();
+        (a_num / a_den)
+            .cmp(&(b_num / b_den))
+            .then(((a_num % a_den) * b_den).cmp(&((b_num % b_den) * a_den)))
+    }
+}
+
+impl From<u64> for BladerfRationalRate {
+    fn from(integer: u64) -> Self {
+        Self::from_integer(integer)
+    }
+}
+
+impl From<&BladerfRationalRate> for f64 {
+    fn from(rate: &BladerfRationalRate) -> Self {
+        rate.as_f64()
+    }
+}
+
+impl std::fmt::Display for BladerfRationalRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}/{}", self.integer, self.num, self.den)
+    }
+}
+
+/**
+ * Range structure
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BladerfRange {
+    /**< Minimum value */
+    pub min: i64,
+    /**< Maximum value */
+    pub max: i64,
+    /**< Step of value */
+    pub step: i64,
+    /**< Unit scale */
+    pub scale: f32,
 }
 
 #[repr(u8)]
@@ -205,3 +761,116 @@ impl Into<u8> for DescriptorTypes {
 //         Ok(response)
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(integer: u64, num: u64, den: u64) -> BladerfRationalRate {
+        BladerfRationalRate::new(integer, num, den)
+    }
+
+    fn assert_terms(rate: BladerfRationalRate, integer: u64, num: u64, den: u64) {
+        assert_eq!((rate.integer, rate.num, rate.den), (integer, num, den));
+    }
+
+    #[test]
+    fn rational_rate_add() {
+        assert_terms(rate(1, 1, 2) + rate(2, 1, 3), 3, 5, 6);
+        assert_terms(rate(1, 1, 2) + rate(0, 1, 2), 2, 0, 1);
+        assert_terms(rate(0, 0, 1) + rate(7, 0, 1), 7, 0, 1);
+        assert!(rate(u64::MAX, 0, 1).checked_add(rate(1, 0, 1)).is_none());
+    }
+
+    #[test]
+    fn rational_rate_sub() {
+        assert_terms(rate(3, 5, 6) - rate(2, 1, 3), 1, 1, 2);
+        assert_terms(rate(5, 1, 4) - rate(5, 1, 4), 0, 0, 1);
+        assert!(rate(1, 0, 1).checked_sub(rate(1, 1, 3)).is_none());
+        assert!(rate(0, 1, 3).checked_sub(rate(0, 1, 2)).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn rational_rate_sub_negative_panics() {
+        let _ = rate(1, 0, 1) - rate(2, 0, 1);
+    }
+
+    #[test]
+    fn rational_rate_mul_div() {
+        assert_terms(rate(1, 1, 3) * 3, 4, 0, 1);
+        assert_terms(rate(10, 0, 1) / 4, 2, 1, 2);
+        assert_terms(rate(1, 1, 2) / 3, 0, 1, 2);
+        assert!(rate(u64::MAX, 0, 1).checked_mul(2).is_none());
+        assert!(rate(1, 0, 1).checked_div(0).is_none());
+        /* Denominator no longer fits in 64 bits */
+        assert!(rate(0, 1, u64::MAX).checked_div(u64::MAX).is_none());
+    }
+
+    #[test]
+    fn rational_rate_cmp() {
+        assert_eq!(rate(1, 1, 2), rate(1, 2, 4));
+        assert_eq!(rate(1, 3, 2), rate(2, 1, 2));
+        assert!(rate(1, 1, 3) < rate(1, 1, 2));
+        assert!(rate(2, 0, 1) > rate(1, 999, 1000));
+        assert!(rate(u64::MAX, u64::MAX - 1, u64::MAX) > rate(u64::MAX, u64::MAX - 2, u64::MAX));
+    }
+
+    #[test]
+    fn rational_rate_reduce() {
+        let mut r = rate(1, 10, 4);
+        r.reduce();
+        assert_terms(r, 3, 1, 2);
+
+        assert_terms(BladerfRationalRate::from_f64(2.25, 1000), 2, 1, 4);
+    }
+
+    fn iq(gain: i16, phase: i16) -> IqCorrection {
+        IqCorrection { gain, phase }
+    }
+
+    #[test]
+    fn iq_correction_from_db_deg() {
+        assert_eq!(IqCorrection::from_db_deg(0.0, 0.0), iq(0, 0));
+        assert_eq!(
+            IqCorrection::from_db_deg(20.0 * 2f64.log10(), 5.0),
+            iq(4096, 2048)
+        );
+        assert_eq!(
+            IqCorrection::from_db_deg(-20.0 * 2f64.log10(), -5.0),
+            iq(-2048, -2048)
+        );
+
+        /* Out of range values are clamped to full scale */
+        assert_eq!(IqCorrection::from_db_deg(20.0, 45.0), iq(4096, 4096));
+        assert_eq!(IqCorrection::from_db_deg(-100.0, -45.0), iq(-4096, -4096));
+        assert_eq!(
+            IqCorrection::from_db_deg(f64::NEG_INFINITY, 0.0),
+            iq(-BLADERF_IQ_CORR_MAX, 0)
+        );
+    }
+
+    #[test]
+    fn iq_correction_to_db_deg() {
+        assert_eq!(iq(0, 0).gain_db(), 0.0);
+        assert!((iq(4096, 0).gain_db() - 20.0 * 2f64.log10()).abs() < 1e-9);
+        assert!((iq(2048, 0).gain_db() - 20.0 * 1.5f64.log10()).abs() < 1e-9);
+        assert_eq!(iq(-4096, 0).gain_db(), f64::NEG_INFINITY);
+
+        assert_eq!(iq(0, 4096).phase_deg(), BLADERF_IQ_CORR_PHASE_MAX_DEG);
+        assert_eq!(iq(0, -2048).phase_deg(), -5.0);
+
+        for correction in [
+            iq(-4000, -4096),
+            iq(-1, 1),
+            iq(1, -1),
+            iq(2048, 100),
+            iq(4096, 4096),
+        ] {
+            assert_eq!(
+                IqCorrection::from_db_deg(correction.gain_db(), correction.phase_deg()),
+                correction
+            );
+        }
+    }
+}
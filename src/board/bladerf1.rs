@@ -1,6 +1,18 @@
 #![allow(private_interfaces)]
 
+pub mod capabilities;
+pub mod dc_cal_table;
+pub mod expansion;
+pub mod smb_clock;
+pub mod stream;
+pub mod trigger;
+pub mod xb100;
+pub mod xb200;
+pub mod xb300;
+
 use std::cmp::PartialEq;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 //use crate::backend::nusb::NusbBackend;
 //use crate::backend::rusb::RusbBackend;
@@ -8,24 +20,56 @@ use std::time::Duration;
 use anyhow::{anyhow, Result};
 use futures_lite::future::block_on;
 use nusb::descriptors::Configuration;
-use nusb::transfer::{ControlOut, ControlType, Recipient};
+use nusb::transfer::{ControlIn, ControlOut, ControlType, Recipient};
 use nusb::{Device, Interface};
 
 use crate::bladerf::BladerfGainMode::{BladerfGainDefault, BladerfGainMgc};
 use crate::bladerf::{
-    BladeRf, BladerfGainMode, DescriptorTypes, BLADERF_MODULE_RX, BLADERF_MODULE_TX,
+    BladeRf, BladerfDevSpeed, BladerfDirection, BladerfFormat, BladerfGainMode, BladerfLoopback,
+    BladerfRange, BladerfRationalRate, BladerfVersion, DescriptorTypes, IqCorrection, RxMux,
+    StringDescriptors, BLADERF_IQ_CORR_MAX, BLADERF_MODULE_RX, BLADERF_MODULE_TX,
+};
+use crate::board::bladerf1::capabilities::{
+    fpga_capabilities, fw_capabilities, have_cap, BLADERF_CAP_AGC_DC_LUT, BLADERF_CAP_FW_LOOPBACK,
+    BLADERF_CAP_PKT_HANDLER_FMT, BLADERF_CAP_TIMESTAMPS,
 };
+use crate::board::bladerf1::dc_cal_table::{DcCalEntry, DcCalTable, DC_CAL_TBL_VERSION_AGC};
+use crate::board::bladerf1::expansion::ExpansionBoard;
+use crate::board::bladerf1::xb200::{BladerfXb200Path, BLADERF_XB200_LO_FREQUENCY, XB200};
 use crate::hardware::dac161s055::DAC161S055;
 use crate::hardware::lms6002d::LMS6002D;
 use crate::hardware::si5338::SI5338;
+use crate::nios::access::NiosFormat;
+use crate::nios::bus::NiosBus;
 use crate::nios::constants::{
-    NIOS_PKT_8X32_TARGET_CONTROL, NIOS_PKT_FLAG_READ, NIOS_PKT_FLAG_WRITE,
+    NIOS_PKT_8X16_ADDR_AGC_DC_I_MAX, NIOS_PKT_8X16_ADDR_AGC_DC_I_MID,
+    NIOS_PKT_8X16_ADDR_AGC_DC_I_MIN, NIOS_PKT_8X16_ADDR_AGC_DC_Q_MAX,
+    NIOS_PKT_8X16_ADDR_AGC_DC_Q_MID, NIOS_PKT_8X16_ADDR_AGC_DC_Q_MIN,
+    NIOS_PKT_8X16_ADDR_IQ_CORR_RX_GAIN, NIOS_PKT_8X16_ADDR_IQ_CORR_RX_PHASE,
+    NIOS_PKT_8X16_ADDR_IQ_CORR_TX_GAIN, NIOS_PKT_8X16_ADDR_IQ_CORR_TX_PHASE,
+    NIOS_PKT_8X16_TARGET_AGC_CORR, NIOS_PKT_8X16_TARGET_IQ_CORR, NIOS_PKT_8X32_TARGET_CONTROL,
+    NIOS_PKT_8X32_TARGET_VERSION, NIOS_PKT_8X64_TARGET_TIMESTAMP, NIOS_PKT_8X64_TIMESTAMP_RX,
+    NIOS_PKT_8X64_TIMESTAMP_TX, NIOS_PKT_FLAG_READ, NIOS_PKT_FLAG_WRITE,
 };
-use crate::nios::packet8x32::NiosPacket8x32;
-use crate::nios::Nios;
+use crate::nios::packet::NiosPacket8x32;
+use crate::nios::packet::NiosPacket8x64;
+use crate::nios::{Nios, NiosInterface};
 use crate::usb::UsbBackend;
 use crate::{bladerf_channel_rx, bladerf_channel_tx};
 
+const ENDPOINT_OUT: u8 = 0x02;
+const ENDPOINT_IN: u8 = 0x82;
+
+/* FX3 vendor requests */
+const BLADE_USB_CMD_RF_RX: u8 = 4;
+const BLADE_USB_CMD_RF_TX: u8 = 5;
+const BLADE_USB_CMD_SET_LOOPBACK: u8 = 113;
+const BLADE_USB_CMD_GET_LOOPBACK: u8 = 114;
+
+/* USB interface alternate settings */
+const USB_IF_NULL: u8 = 0;
+const USB_IF_RF_LINK: u8 = 1;
+
 #[derive(thiserror::Error, Debug)]
 pub enum BladeRfError {
     /// Device not found.
@@ -158,6 +202,9 @@ const BLADERF_GPIO_PACKET_CORE_PRESENT: u32 = 1 << 28;
 
 pub const BLADERF_SAMPLERATE_MIN: u64 = 80000;
 
+/** Maximum recommended sample rate, in samples per second */
+pub const BLADERF_SAMPLERATE_REC_MAX: u64 = 40000000;
+
 /** Minimum tunable frequency (without an XB-200 attached), in Hz
 *
 * \deprecated Use bladerf_get_frequency_range()
@@ -206,10 +253,18 @@ pub struct BladeRf1 {
     device: Device,
     #[allow(dead_code)]
     pub interface: Interface,
+    nios: NiosInterface,
     lms: LMS6002D,
     si5338: SI5338,
     dac: DAC161S055,
-    //xb200: Option<XB200>,
+    dc_cal_rx: Mutex<Option<DcCalTable>>,
+    dc_cal_tx: Mutex<Option<DcCalTable>>,
+    fpga_version: OnceLock<BladerfVersion>,
+    fw_version: OnceLock<BladerfVersion>,
+    speed: BladerfDevSpeed,
+    module_format: Mutex<[Option<BladerfFormat>; 2]>,
+    xb: Mutex<ExpansionBoard>,
+    xb200: Mutex<Option<XB200>>,
 }
 // We use the Builder pattern together with the type-state pattern here to model the flow of creating a BladeRf1 instance.
 // See for example: https://cliffle.com/blog/rust-typestate/
@@ -223,47 +278,182 @@ impl BladeRf1 {
     }
 
     fn config_gpio_read(&self) -> Result<u32> {
-        const ENDPOINT_OUT: u8 = 0x02;
-        const ENDPOINT_IN: u8 = 0x82;
-
         let mut request = NiosPacket8x32::new();
         request.set(NIOS_PKT_8X32_TARGET_CONTROL, NIOS_PKT_FLAG_READ, 0x0, 0x0);
         let response = self
-            .interface
+            .nios
             .nios_send(ENDPOINT_IN, ENDPOINT_OUT, request.into_vec())?;
-        Ok(NiosPacket8x32::reuse(response).data())
+        Ok(NiosPacket8x32::from_vec(response)?.data())
     }
 
-    fn config_gpio_write(&self, mut data: u32) -> Result<()> {
-        const ENDPOINT_OUT: u8 = 0x02;
-        const ENDPOINT_IN: u8 = 0x82;
+    /// Get the version of the loaded FPGA image. The value is read once and cached.
+    pub fn fpga_version(&self) -> Result<BladerfVersion> {
+        if let Some(version) = self.fpga_version.get() {
+            return Ok(*version);
+        }
 
-        enum DeviceSpeed {
-            BladerfDeviceSpeedUnknown,
-            BladerfDeviceSpeedHigh,
-            BladerfDeviceSpeedSuper,
+        /* The packet format is not known yet, but every FPGA image implements the
+         * legacy packet handler */
+        let mut request = NiosPacket8x32::new();
+        request.set(NIOS_PKT_8X32_TARGET_VERSION, NIOS_PKT_FLAG_READ, 0x0, 0x0);
+        let response = self
+            .nios
+            .legacy_send(ENDPOINT_IN, ENDPOINT_OUT, request.into_vec())?;
+        let regval = NiosPacket8x32::from_vec(response)?.data();
+
+        let version = BladerfVersion {
+            major: ((regval >> 24) & 0xff) as u16,
+            minor: ((regval >> 16) & 0xff) as u16,
+            patch: (regval & 0xffff) as u16,
+        };
+        log::debug!("Read FPGA version: {}", version);
+
+        if have_cap(fpga_capabilities(&version), BLADERF_CAP_PKT_HANDLER_FMT) {
+            self.nios.set_legacy(false);
+        } else {
+            log::info!("Legacy packet handler implementation detected");
+            self.nios.set_legacy(true);
         }
 
-        // TODO: Get usb speed dynamically
-        let device_speed: DeviceSpeed = DeviceSpeed::BladerfDeviceSpeedSuper;
-        match device_speed {
-            DeviceSpeed::BladerfDeviceSpeedUnknown => {
-                println!("DeviceSpeed::BladerfDeviceSpeedUnknown");
-            }
-            DeviceSpeed::BladerfDeviceSpeedHigh => {
-                println!("DeviceSpeed::BladerfDeviceSpeedUnknown");
-                data = data | (BLADERF_GPIO_FEATURE_SMALL_DMA_XFER as u32);
-            }
-            DeviceSpeed::BladerfDeviceSpeedSuper => {
-                println!("DeviceSpeed::BladerfDeviceSpeedUnknown");
-                data = data & (!BLADERF_GPIO_FEATURE_SMALL_DMA_XFER as u32);
-            }
+        Ok(*self.fpga_version.get_or_init(|| version))
+    }
+
+    /// Get the version of the FX3 firmware. The value is read once and cached.
+    pub fn fw_version(&self) -> Result<BladerfVersion> {
+        if let Some(version) = self.fw_version.get() {
+            return Ok(*version);
+        }
+
+        /* The firmware reports e.g. "2.4.0" or "1.9.1-git-4e8ae8f" */
+        let descriptor = self.get_string_descriptor(StringDescriptors::Fx3Firmware.into())?;
+        let mut fields = descriptor
+            .split(|c: char| !c.is_ascii_digit())
+            .take(3)
+            .map(|field| field.parse::<u16>());
+
+        let mut next_field = || -> Result<u16> {
+            fields
+                .next()
+                .and_then(|field| field.ok())
+                .ok_or_else(|| anyhow!("Invalid firmware version string: \"{}\"", descriptor))
+        };
+        let version = BladerfVersion {
+            major: next_field()?,
+            minor: next_field()?,
+            patch: next_field()?,
+        };
+        log::debug!("Read firmware version: {}", version);
+
+        Ok(*self.fw_version.get_or_init(|| version))
+    }
+
+    /// Get the capability mask of the device, see the `BLADERF_CAP_*` constants in [`mod@capabilities`].
+    pub fn capabilities(&self) -> Result<u64> {
+        Ok(fpga_capabilities(&self.fpga_version()?) | fw_capabilities(&self.fw_version()?))
+    }
+
+    fn vendor_cmd_int_wvalue(&self, cmd: u8, wvalue: u16) -> Result<i32> {
+        let response = block_on(self.interface.control_in(ControlIn {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: cmd,
+            value: wvalue,
+            index: 0x00,
+            length: 4,
+        }))
+        .into_result()?;
+
+        let bytes: [u8; 4] = response
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow!("Short response to vendor command {}", cmd))?;
+        Ok(i32::from_le_bytes(bytes))
+    }
+
+    fn vendor_cmd_int(&self, cmd: u8) -> Result<i32> {
+        self.vendor_cmd_int_wvalue(cmd, 0)
+    }
+
+    fn set_firmware_loopback(&self, enable: bool) -> Result<()> {
+        self.vendor_cmd_int_wvalue(BLADE_USB_CMD_SET_LOOPBACK, enable as u16)?;
+
+        /* Re-select the RF link interface for the change to take effect */
+        self.interface.set_alt_setting(USB_IF_NULL)?;
+        self.interface.set_alt_setting(USB_IF_RF_LINK)?;
+        Ok(())
+    }
+
+    fn get_firmware_loopback(&self) -> Result<bool> {
+        Ok(self.vendor_cmd_int(BLADE_USB_CMD_GET_LOOPBACK)? != 0)
+    }
+
+    fn set_agc_dc_correction(&self, entry: &DcCalEntry) -> Result<()> {
+        let values = [
+            (NIOS_PKT_8X16_ADDR_AGC_DC_Q_MAX, entry.max_dc_q),
+            (NIOS_PKT_8X16_ADDR_AGC_DC_I_MAX, entry.max_dc_i),
+            (NIOS_PKT_8X16_ADDR_AGC_DC_Q_MID, entry.mid_dc_q),
+            (NIOS_PKT_8X16_ADDR_AGC_DC_I_MID, entry.mid_dc_i),
+            (NIOS_PKT_8X16_ADDR_AGC_DC_Q_MIN, entry.min_dc_q),
+            (NIOS_PKT_8X16_ADDR_AGC_DC_I_MIN, entry.min_dc_i),
+        ];
+
+        for (addr, value) in values {
+            <(u8, u16)>::write(
+                &self.nios,
+                NIOS_PKT_8X16_TARGET_AGC_CORR,
+                addr,
+                value as u16,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Read a register of an FPGA target. The packet format is selected by the address and
+    /// data types, e.g. `nios_read::<u8, u32>` uses 8x32 packets. Targets
+    /// `NIOS_PKT_TARGET_USR1` through `NIOS_PKT_TARGET_USR128` are reserved for custom
+    /// FPGA blocks.
+    pub fn nios_read<A, D>(&self, target_id: u8, addr: A) -> Result<D>
+    where
+        (A, D): NiosFormat<Addr = A, Data = D>,
+    {
+        <(A, D)>::read(&self.nios, target_id, addr)
+    }
+
+    /// Write a register of an FPGA target. See [`BladeRf1::nios_read`].
+    pub fn nios_write<A, D>(&self, target_id: u8, addr: A, data: D) -> Result<()>
+    where
+        (A, D): NiosFormat<Addr = A, Data = D>,
+    {
+        <(A, D)>::write(&self.nios, target_id, addr, data)
+    }
+
+    /* 32x32 targets use the address field as a bit mask */
+    fn nios_32x32_masked_read(&self, target_id: u8, mask: u32) -> Result<u32> {
+        <(u32, u32)>::read(&self.nios, target_id, mask)
+    }
+
+    fn nios_32x32_masked_write(&self, target_id: u8, mask: u32, data: u32) -> Result<()> {
+        <(u32, u32)>::write(&self.nios, target_id, mask, data)
+    }
+
+    /// USB bus speed the device is connected at, as detected when the device was opened.
+    pub fn device_speed(&self) -> BladerfDevSpeed {
+        self.speed
+    }
+
+    fn config_gpio_write(&self, mut data: u32) -> Result<()> {
+        /* The FPGA needs smaller DMA transfers when not connected at SuperSpeed.
+         * An unknown speed is treated as SuperSpeed. */
+        if self.speed == BladerfDevSpeed::BladerfDeviceSpeedHigh {
+            data |= BLADERF_GPIO_FEATURE_SMALL_DMA_XFER as u32;
+        } else {
+            data &= !(BLADERF_GPIO_FEATURE_SMALL_DMA_XFER as u32);
         }
 
         let mut request = NiosPacket8x32::new();
         request.set(NIOS_PKT_8X32_TARGET_CONTROL, NIOS_PKT_FLAG_WRITE, 0x0, data);
         let _response = self
-            .interface
+            .nios
             .nios_send(ENDPOINT_IN, ENDPOINT_OUT, request.into_vec())?;
         Ok(())
     }
@@ -275,6 +465,9 @@ impl BladeRf1 {
         self.interface.set_alt_setting(0x01)?;
         println!("[*] Init - Set Alt Setting to 0x01");
 
+        /* Select the packet format before any other FPGA access */
+        self.fpga_version()?;
+
         let cfg = self.config_gpio_read()?;
         if (cfg & 0x7f) == 0 {
             println!("[*] Init - Default GPIO value \"{cfg}\" found - initializing device");
@@ -400,20 +593,324 @@ impl BladeRf1 {
         Ok(())
     }
 
-    pub fn set_frequency(&self, channel: u8, frequency: u64) -> Result<()> {
-        //let dc_cal = if channel == bladerf_channel_rx!(0) { cal_dc.rx } else { cal.dc_tx };
+    fn check_sample_rate_channel(channel: u8) -> Result<()> {
+        if channel != BLADERF_MODULE_RX && channel != BLADERF_MODULE_TX {
+            return Err(anyhow!("Invalid channel: {}", channel));
+        }
+        Ok(())
+    }
+
+    /* Check a requested rate against the supported range, comparing the exact rational value */
+    fn check_sample_rate_range(&self, channel: u8, rate: &BladerfRationalRate) -> Result<()> {
+        if rate.den == 0 {
+            return Err(anyhow!("Rational sample rate denominator must be non-zero"));
+        }
+
+        let range = self.get_sample_rate_range(channel)?;
+        if *rate < BladerfRationalRate::from_integer(range.min as u64)
+            || *rate > BladerfRationalRate::from_integer(range.max as u64)
+        {
+            return Err(anyhow!(
+                "Sample rate {} on channel {} is outside of the supported range [{}, {}]",
+                rate.as_f64(),
+                channel,
+                range.min,
+                range.max
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set the sample rate of a channel. Returns the actual integer sample rate set.
+    pub fn set_sample_rate(&self, channel: u8, rate: u32) -> Result<u32> {
+        let actual = self
+            .set_rational_sample_rate(channel, &BladerfRationalRate::from_integer(rate as u64))?;
+
+        if actual.num != 0 {
+            log::warn!("Non-integer sample rate set from integer sample rate, truncating output.");
+        }
+
+        Ok(actual.integer as u32)
+    }
+
+    /// Set the sample rate of a channel as a rational value. Returns the actual rate set.
+    pub fn set_rational_sample_rate(
+        &self,
+        channel: u8,
+        rate: &BladerfRationalRate,
+    ) -> Result<BladerfRationalRate> {
+        Self::check_sample_rate_channel(channel)?;
+        self.check_sample_rate_range(channel, rate)?;
+
+        let mut rate = rate.clone();
+        rate.reduce();
+
+        self.si5338.set_rational_sample_rate(channel, &mut rate)
+    }
+
+    pub fn get_sample_rate(&self, channel: u8) -> Result<u32> {
+        Self::check_sample_rate_channel(channel)?;
+        self.si5338.get_sample_rate(channel)
+    }
+
+    pub fn get_rational_sample_rate(&self, channel: u8) -> Result<BladerfRationalRate> {
+        Self::check_sample_rate_channel(channel)?;
+        self.si5338.get_rational_sample_rate(channel)
+    }
+
+    /// Range of sample rates supported on a channel.
+    pub fn get_sample_rate_range(&self, channel: u8) -> Result<BladerfRange> {
+        Self::check_sample_rate_channel(channel)?;
+        Ok(BladerfRange {
+            min: BLADERF_SAMPLERATE_MIN as i64,
+            max: BLADERF_SAMPLERATE_REC_MAX as i64,
+            step: 1,
+            scale: 1.0,
+        })
+    }
+
+    /// Set the RX and TX sample rates together, for full-duplex operation.
+    ///
+    /// Both rates are validated against the SI5338 multisynth constraints before either is
+    /// programmed, so an invalid pair leaves the current configuration untouched. Returns
+    /// the exact (RX, TX) rates achieved.
+    pub fn set_sample_rates(
+        &self,
+        rx: &BladerfRationalRate,
+        tx: &BladerfRationalRate,
+    ) -> Result<(BladerfRationalRate, BladerfRationalRate)> {
+        let mut expected = Vec::with_capacity(2);
+        for (channel, rate) in [(BLADERF_MODULE_RX, rx), (BLADERF_MODULE_TX, tx)] {
+            self.check_sample_rate_range(channel, rate)?;
+
+            let actual = SI5338::calculate_sample_rate(channel, rate)
+                .map_err(|e| anyhow!("Invalid sample rate on channel {}: {}", channel, e))?;
+            if actual != *rate {
+                log::debug!(
+                    "Sample rate {} on channel {} cannot be produced exactly, using {}",
+                    rate,
+                    channel,
+                    actual
+                );
+            }
+            expected.push(actual);
+        }
+
+        Self::check_sample_rate_conflicts(&expected[0], &expected[1]);
 
+        let rx_actual = self.set_rational_sample_rate(BLADERF_MODULE_RX, rx)?;
+        let tx_actual = self.set_rational_sample_rate(BLADERF_MODULE_TX, tx)?;
+
+        Ok((rx_actual, tx_actual))
+    }
+
+    /* Both sample clock multisynths are fed from the same fixed-frequency VCO, so any
+     * pair of valid rates can be programmed and differing rates are a valid
+     * configuration. They are only phase-locked to each other when one rate is an
+     * integer multiple of the other, however. */
+    fn check_sample_rate_conflicts(rx: &BladerfRationalRate, tx: &BladerfRationalRate) {
+        if rx == tx {
+            return;
+        }
+
+        let (fast, slow) = if rx > tx { (rx, tx) } else { (tx, rx) };
+        let fast_num = (fast.integer as u128 * fast.den as u128 + fast.num as u128)
+            .checked_mul(slow.den as u128);
+        let slow_num = (slow.integer as u128 * slow.den as u128 + slow.num as u128)
+            .checked_mul(fast.den as u128);
+
+        match (fast_num, slow_num) {
+            (Some(fast_num), Some(slow_num))
+                if slow_num != 0 && fast_num.is_multiple_of(slow_num) =>
+            {
+                log::debug!(
+                    "RX ({}) and TX ({}) sample rates differ; RX and TX timestamps will advance \
+                     at different rates",
+                    rx,
+                    tx
+                );
+            }
+            _ => {
+                log::debug!(
+                    "RX ({}) and TX ({}) sample rates are not integer related; the multisynth \
+                     outputs will drift in phase and RX/TX timestamps cannot be correlated",
+                    rx,
+                    tx
+                );
+            }
+        }
+    }
+
+    pub fn set_frequency(&self, channel: u8, frequency: u64) -> Result<()> {
         println!(
             "Setting Frequency on channel {} to {}Hz",
             channel, frequency
         );
 
-        // Ommit XB200 settings here
+        let mut frequency = frequency;
+        if self.expansion_get_attached() == ExpansionBoard::Xb200 {
+            if frequency < BLADERF_FREQUENCY_MIN as u64 {
+                self.xb200_set_path(channel, BladerfXb200Path::BladerfXb200Mix)?;
+                self.xb200_auto_filter_selection(channel, frequency)?;
+
+                frequency = BLADERF_XB200_LO_FREQUENCY - frequency;
+            } else {
+                self.xb200_set_path(channel, BladerfXb200Path::BladerfXb200Bypass)?;
+            }
+        }
 
         self.lms.set_frequency(channel, frequency as u32)?;
+
+        let dc_cal = if channel == bladerf_channel_rx!(0) {
+            &self.dc_cal_rx
+        } else {
+            &self.dc_cal_tx
+        };
+        let entry = dc_cal
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|dc_cal| dc_cal.entry_for(frequency as u32));
+
+        if let Some(entry) = entry {
+            self.lms.set_dc_offset_i(channel, entry.dc_i)?;
+            self.lms.set_dc_offset_q(channel, entry.dc_q)?;
+
+            if channel == bladerf_channel_rx!(0)
+                && have_cap(self.capabilities()?, BLADERF_CAP_AGC_DC_LUT)
+            {
+                self.set_agc_dc_correction(&entry)?;
+
+                log::debug!(
+                    "Set AGC DC offset cal (I, Q) to: Max ({}, {})  Mid ({}, {}) Min ({}, {})",
+                    entry.max_dc_i,
+                    entry.max_dc_q,
+                    entry.mid_dc_i,
+                    entry.mid_dc_q,
+                    entry.min_dc_i,
+                    entry.min_dc_q
+                );
+            }
+
+            log::debug!(
+                "Set {} DC offset cal (I, Q) to: ({}, {})",
+                if channel == bladerf_channel_rx!(0) {
+                    "RX"
+                } else {
+                    "TX"
+                },
+                entry.dc_i,
+                entry.dc_q
+            );
+        }
         Ok(())
     }
 
+    /// Get the frequency a channel is tuned to, taking the XB-200 mixer path into account.
+    pub fn get_frequency(&self, channel: u8) -> Result<u64> {
+        let f = self.lms.get_frequency(channel)?;
+        if f.x == 0 {
+            /* If we see this, it's most often an indication that communication
+             * with the LMS6002D is not occuring correctly */
+            return Err(anyhow!("LMSFREQ returned x=0, which is invalid"));
+        }
+
+        let mut frequency = LMS6002D::frequency_to_hz(&f) as u64;
+
+        if self.expansion_get_attached() == ExpansionBoard::Xb200
+            && self.xb200_get_path(channel)? == BladerfXb200Path::BladerfXb200Mix
+        {
+            frequency = BLADERF_XB200_LO_FREQUENCY - frequency;
+        }
+
+        Ok(frequency)
+    }
+
+    /// Load a DC calibration table from a `.tbl` file. See [`BladeRf1::set_dc_cal_table`].
+    pub fn load_dc_cal_table<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.set_dc_cal_table(DcCalTable::load(path)?);
+        Ok(())
+    }
+
+    /// Use a DC calibration table for the channel it was created for. The interpolated
+    /// correction is applied on every subsequent call to [`BladeRf1::set_frequency`].
+    pub fn set_dc_cal_table(&self, table: DcCalTable) {
+        if table.channel() == BLADERF_MODULE_RX {
+            *self.dc_cal_rx.lock().unwrap() = Some(table);
+        } else {
+            *self.dc_cal_tx.lock().unwrap() = Some(table);
+        }
+    }
+
+    /// Stop applying DC calibration values on frequency changes of a channel.
+    pub fn clear_dc_cal_table(&self, channel: u8) -> Result<()> {
+        match channel {
+            BLADERF_MODULE_RX => *self.dc_cal_rx.lock().unwrap() = None,
+            BLADERF_MODULE_TX => *self.dc_cal_tx.lock().unwrap() = None,
+            _ => return Err(anyhow!("Invalid channel: {}", channel)),
+        }
+        Ok(())
+    }
+
+    /// DC calibration table currently used for a channel, if any.
+    pub fn dc_cal_table(&self, channel: u8) -> Option<DcCalTable> {
+        match channel {
+            BLADERF_MODULE_RX => self.dc_cal_rx.lock().unwrap().clone(),
+            BLADERF_MODULE_TX => self.dc_cal_tx.lock().unwrap().clone(),
+            _ => None,
+        }
+    }
+
+    /// RF transceiver driver, for register-level debugging.
+    pub fn lms(&self) -> &LMS6002D {
+        &self.lms
+    }
+
+    /// Clock generator driver, for register-level debugging.
+    pub fn si5338(&self) -> &SI5338 {
+        &self.si5338
+    }
+
+    /// Read a register on the FPGA's Wishbone bus.
+    pub fn wishbone_read(&self, addr: u32) -> Result<u32> {
+        self.nios.wishbone_read(addr)
+    }
+
+    /// Write a register on the FPGA's Wishbone bus.
+    pub fn wishbone_write(&self, addr: u32, data: u32) -> Result<()> {
+        self.nios.wishbone_write(addr, data)
+    }
+
+    /// Read a sequence of Wishbone registers in one batch.
+    pub fn wishbone_read_batch(&self, addrs: &[u32]) -> Result<Vec<u32>> {
+        self.nios.wishbone_read_batch(addrs)
+    }
+
+    /// Write a sequence of Wishbone registers in one batch, in order.
+    pub fn wishbone_write_batch(&self, writes: &[(u32, u32)]) -> Result<()> {
+        self.nios.wishbone_write_batch(writes)
+    }
+
+    /// Read a register through the FPGA's ADI AXI interface.
+    pub fn adi_axi_read(&self, addr: u32) -> Result<u32> {
+        self.nios.adi_axi_read(addr)
+    }
+
+    /// Write a register through the FPGA's ADI AXI interface.
+    pub fn adi_axi_write(&self, addr: u32, data: u32) -> Result<()> {
+        self.nios.adi_axi_write(addr, data)
+    }
+
+    /// Read a sequence of ADI AXI registers in one batch.
+    pub fn adi_axi_read_batch(&self, addrs: &[u32]) -> Result<Vec<u32>> {
+        self.nios.adi_axi_read_batch(addrs)
+    }
+
+    /// Write a sequence of ADI AXI registers in one batch, in order.
+    pub fn adi_axi_write_batch(&self, writes: &[(u32, u32)]) -> Result<()> {
+        self.nios.adi_axi_write_batch(writes)
+    }
+
     pub fn set_gain_mode(&self, channel: u8, mode: BladerfGainMode) -> Result<()> {
         if channel != BLADERF_MODULE_RX {
             return Err(anyhow!("Operation only supported on RX channel"));
@@ -421,6 +918,27 @@ impl BladeRf1 {
 
         let mut config_gpio = self.config_gpio_read()?;
         if mode == BladerfGainDefault {
+            if !have_cap(self.capabilities()?, BLADERF_CAP_AGC_DC_LUT) {
+                return Err(anyhow!(
+                    "AGC not supported by FPGA {}, use manual gain control",
+                    self.fpga_version()?
+                ));
+            }
+            match self.dc_cal_rx.lock().unwrap().as_ref() {
+                None => {
+                    return Err(anyhow!(
+                        "RX DC calibration table not loaded, use manual gain control"
+                    ))
+                }
+                Some(table) if table.version() < DC_CAL_TBL_VERSION_AGC => {
+                    return Err(anyhow!(
+                        "RX DC calibration table version {} has no AGC values, use manual gain \
+                         control",
+                        table.version()
+                    ))
+                }
+                Some(_) => {}
+            }
             config_gpio |= BLADERF_GPIO_AGC_ENABLE;
         } else if mode == BladerfGainMgc {
             config_gpio &= !BLADERF_GPIO_AGC_ENABLE;
@@ -429,6 +947,291 @@ impl BladeRf1 {
         Ok(self.config_gpio_write(config_gpio)?)
     }
 
+    /// Read the current value of the FPGA timestamp counter of a direction.
+    pub fn get_timestamp(&self, direction: BladerfDirection) -> Result<u64> {
+        let addr = match direction {
+            BladerfDirection::BladerfRx => NIOS_PKT_8X64_TIMESTAMP_RX,
+            BladerfDirection::BladerfTx => NIOS_PKT_8X64_TIMESTAMP_TX,
+        };
+
+        let mut request = NiosPacket8x64::new();
+        request.set(
+            NIOS_PKT_8X64_TARGET_TIMESTAMP,
+            NIOS_PKT_FLAG_READ,
+            addr,
+            0x0,
+        );
+        let response = self
+            .nios
+            .nios_send(ENDPOINT_IN, ENDPOINT_OUT, request.into_vec())?;
+        Ok(NiosPacket8x64::from_vec(response)?.data())
+    }
+
+    /// Enable or disable the RF front end and the FX3 sample path of a channel.
+    pub fn enable_module(&self, channel: u8, enable: bool) -> Result<()> {
+        let (direction, cmd) = match channel {
+            BLADERF_MODULE_RX => (BladerfDirection::BladerfRx, BLADE_USB_CMD_RF_RX),
+            BLADERF_MODULE_TX => (BladerfDirection::BladerfTx, BLADE_USB_CMD_RF_TX),
+            _ => return Err(anyhow!("Invalid channel: {}", channel)),
+        };
+
+        let xb300_tx =
+            channel == BLADERF_MODULE_TX && self.expansion_get_attached() == ExpansionBoard::Xb300;
+
+        if !enable {
+            self.perform_format_deconfig(direction);
+
+            /* Power down the XB-300 PA before the TX path goes quiet */
+            if xb300_tx {
+                self.xb300_tx_enable(false)?;
+            }
+        }
+
+        self.lms.enable_rffe(channel, enable)?;
+
+        let fx3_ret = self.vendor_cmd_int_wvalue(cmd, enable as u16)?;
+        if fx3_ret != 0 {
+            return Err(anyhow!(
+                "FX3 reported error=0x{:x} when {} RF {:?}",
+                fx3_ret,
+                if enable { "enabling" } else { "disabling" },
+                direction
+            ));
+        }
+
+        if enable && xb300_tx {
+            self.xb300_tx_enable(true)?;
+        }
+        Ok(())
+    }
+
+    /* Configure the FPGA for the sample format used on a direction. Both
+     * directions must agree on the use of timestamps. */
+    fn perform_format_config(
+        &self,
+        direction: BladerfDirection,
+        format: BladerfFormat,
+    ) -> Result<()> {
+        let use_timestamps = format == BladerfFormat::BladerfFormatSc16Q11Meta;
+
+        let mut module_format = self.module_format.lock().unwrap();
+        let other = match direction {
+            BladerfDirection::BladerfRx => module_format[BladerfDirection::BladerfTx as usize],
+            BladerfDirection::BladerfTx => module_format[BladerfDirection::BladerfRx as usize],
+        };
+
+        if let Some(other) = other {
+            let other_using_timestamps = other == BladerfFormat::BladerfFormatSc16Q11Meta;
+            if other_using_timestamps != use_timestamps {
+                return Err(anyhow!(
+                    "Format {:?} conflicts with {:?} used by the other direction",
+                    format,
+                    other
+                ));
+            }
+        }
+
+        if use_timestamps && !have_cap(self.capabilities()?, BLADERF_CAP_TIMESTAMPS) {
+            return Err(anyhow!(
+                "Timestamps are not supported by FPGA {}",
+                self.fpga_version()?
+            ));
+        }
+
+        let mut gpio_val = self.config_gpio_read()?;
+        if use_timestamps {
+            gpio_val |= BLADERF_GPIO_TIMESTAMP | BLADERF_GPIO_TIMESTAMP_DIV2;
+        } else {
+            gpio_val &= !(BLADERF_GPIO_TIMESTAMP | BLADERF_GPIO_TIMESTAMP_DIV2);
+        }
+        self.config_gpio_write(gpio_val)?;
+
+        module_format[direction as usize] = Some(format);
+        Ok(())
+    }
+
+    fn perform_format_deconfig(&self, direction: BladerfDirection) {
+        self.module_format.lock().unwrap()[direction as usize] = None;
+    }
+
+    /// Replace RX samples with an incrementing 32-bit counter, for debugging dropped samples.
+    pub fn set_counter_mode(&self, enable: bool) -> Result<()> {
+        let mut gpio_val = self.config_gpio_read()?;
+        if enable {
+            gpio_val |= BLADERF_GPIO_COUNTER_ENABLE as u32;
+        } else {
+            gpio_val &= !(BLADERF_GPIO_COUNTER_ENABLE as u32);
+        }
+        self.config_gpio_write(gpio_val)
+    }
+
+    pub fn get_counter_mode(&self) -> Result<bool> {
+        Ok((self.config_gpio_read()? & BLADERF_GPIO_COUNTER_ENABLE as u32) != 0)
+    }
+
+    /// Select the source of the samples the FPGA delivers to the RX FIFO.
+    pub fn set_rx_mux(&self, mode: RxMux) -> Result<()> {
+        let rx_mux_val = (mode as u32) << BLADERF_GPIO_RX_MUX_SHIFT;
+
+        let mut config_gpio = self.config_gpio_read()?;
+
+        /* Clear out and assign the associated RX mux bits */
+        config_gpio &= !(BLADERF_GPIO_RX_MUX_MASK as u32);
+        config_gpio |= rx_mux_val;
+
+        self.config_gpio_write(config_gpio)
+    }
+
+    pub fn get_rx_mux(&self) -> Result<RxMux> {
+        let config_gpio = self.config_gpio_read()?;
+
+        /* Extract RX mux bits */
+        let val = (config_gpio & BLADERF_GPIO_RX_MUX_MASK as u32) >> BLADERF_GPIO_RX_MUX_SHIFT;
+        match val {
+            0x0 => Ok(RxMux::RxMuxBaseband),
+            0x1 => Ok(RxMux::RxMux12bitCounter),
+            0x2 => Ok(RxMux::RxMux32bitCounter),
+            0x4 => Ok(RxMux::RxMuxDigitalLoopback),
+            _ => Err(anyhow!(
+                "Invalid RX mux mode setting read from device: 0x{:x}",
+                val
+            )),
+        }
+    }
+
+    /// Apply a loopback mode. `BladerfLbNone` restores normal operation.
+    pub fn set_loopback(&self, lb: BladerfLoopback) -> Result<()> {
+        let capabilities = self.capabilities()?;
+
+        if lb == BladerfLoopback::BladerfLbFirmware {
+            if !have_cap(capabilities, BLADERF_CAP_FW_LOOPBACK) {
+                return Err(anyhow!(
+                    "Firmware v1.7.1 or later is required to use firmware loopback"
+                ));
+            }
+
+            /* Samples won't reach the LMS when the device is in firmware
+             * loopback mode. By placing the LMS into a loopback mode, we ensure
+             * that the PAs will be disabled, and remain enabled across
+             * frequency changes.
+             */
+            self.lms
+                .set_loopback_mode(BladerfLoopback::BladerfLbRfLna3)?;
+            self.set_firmware_loopback(true)
+        } else {
+            /* If applicable, ensure FW loopback is disabled */
+            if have_cap(capabilities, BLADERF_CAP_FW_LOOPBACK) {
+                /* Query first, as the implementation of setting the mode
+                 * may interrupt running streams. */
+                if self.get_firmware_loopback()? {
+                    self.set_firmware_loopback(false)?;
+                }
+            }
+
+            self.lms.set_loopback_mode(lb)
+        }
+    }
+
+    /// Get the currently active loopback mode.
+    pub fn get_loopback(&self) -> Result<BladerfLoopback> {
+        if have_cap(self.capabilities()?, BLADERF_CAP_FW_LOOPBACK)
+            && self.get_firmware_loopback()?
+        {
+            return Ok(BladerfLoopback::BladerfLbFirmware);
+        }
+
+        self.lms.lms_get_loopback_mode()
+    }
+
+    /// Set the LMS6002D DC offset correction for the I and Q branches of a channel.
+    /// Values are normalized to [-2048, 2048] and clamped to the register range.
+    pub fn set_dc_offset(&self, channel: u8, i: i16, q: i16) -> Result<()> {
+        if channel != BLADERF_MODULE_RX && channel != BLADERF_MODULE_TX {
+            return Err(anyhow!("Invalid channel: {}", channel));
+        }
+
+        self.lms.set_dc_offset_i(channel, i)?;
+        self.lms.set_dc_offset_q(channel, q)?;
+        Ok(())
+    }
+
+    /// Get the LMS6002D DC offset correction (I, Q) of a channel, normalized to [-2048, 2048].
+    pub fn get_dc_offset(&self, channel: u8) -> Result<(i16, i16)> {
+        if channel != BLADERF_MODULE_RX && channel != BLADERF_MODULE_TX {
+            return Err(anyhow!("Invalid channel: {}", channel));
+        }
+
+        let i = self.lms.get_dc_offset_i(channel)?;
+        let q = self.lms.get_dc_offset_q(channel)?;
+        Ok((i, q))
+    }
+
+    /// Set the FPGA IQ gain and phase imbalance correction of a channel.
+    pub fn set_iq_correction(&self, channel: u8, correction: IqCorrection) -> Result<()> {
+        let (gain_addr, phase_addr) = match channel {
+            BLADERF_MODULE_RX => (
+                NIOS_PKT_8X16_ADDR_IQ_CORR_RX_GAIN,
+                NIOS_PKT_8X16_ADDR_IQ_CORR_RX_PHASE,
+            ),
+            BLADERF_MODULE_TX => (
+                NIOS_PKT_8X16_ADDR_IQ_CORR_TX_GAIN,
+                NIOS_PKT_8X16_ADDR_IQ_CORR_TX_PHASE,
+            ),
+            _ => return Err(anyhow!("Invalid channel: {}", channel)),
+        };
+
+        let correction = IqCorrection {
+            gain: correction
+                .gain
+                .clamp(-BLADERF_IQ_CORR_MAX, BLADERF_IQ_CORR_MAX),
+            phase: correction
+                .phase
+                .clamp(-BLADERF_IQ_CORR_MAX, BLADERF_IQ_CORR_MAX),
+        };
+
+        log::debug!(
+            "Setting IQ correction on channel {}: gain {} ({:.3}dB), phase {} ({:.3}deg)",
+            channel,
+            correction.gain,
+            correction.gain_db(),
+            correction.phase,
+            correction.phase_deg()
+        );
+
+        <(u8, u16)>::write(
+            &self.nios,
+            NIOS_PKT_8X16_TARGET_IQ_CORR,
+            gain_addr,
+            correction.gain as u16,
+        )?;
+        <(u8, u16)>::write(
+            &self.nios,
+            NIOS_PKT_8X16_TARGET_IQ_CORR,
+            phase_addr,
+            correction.phase as u16,
+        )
+    }
+
+    /// Get the FPGA IQ gain and phase imbalance correction of a channel.
+    pub fn get_iq_correction(&self, channel: u8) -> Result<IqCorrection> {
+        let (gain_addr, phase_addr) = match channel {
+            BLADERF_MODULE_RX => (
+                NIOS_PKT_8X16_ADDR_IQ_CORR_RX_GAIN,
+                NIOS_PKT_8X16_ADDR_IQ_CORR_RX_PHASE,
+            ),
+            BLADERF_MODULE_TX => (
+                NIOS_PKT_8X16_ADDR_IQ_CORR_TX_GAIN,
+                NIOS_PKT_8X16_ADDR_IQ_CORR_TX_PHASE,
+            ),
+            _ => return Err(anyhow!("Invalid channel: {}", channel)),
+        };
+
+        let gain = <(u8, u16)>::read(&self.nios, NIOS_PKT_8X16_TARGET_IQ_CORR, gain_addr)? as i16;
+        let phase = <(u8, u16)>::read(&self.nios, NIOS_PKT_8X16_TARGET_IQ_CORR, phase_addr)? as i16;
+
+        Ok(IqCorrection { gain, phase })
+    }
+
     // static int bladerf1_set_frequency(struct bladerf *dev,
     // bladerf_channel ch,
     // bladerf_frequency frequency)
@@ -814,18 +1617,49 @@ impl BladeRf1Builder<WithDevice> {
         //Box<dyn BladeRf>
         let device = self.data.device.clone();
         let interface = device.detach_and_claim_interface(0)?;
-        let lms = LMS6002D::new(interface.clone());
-        let si5338 = SI5338::new(interface.clone());
-        let dac = DAC161S055::new(interface.clone());
+        let nios = NiosInterface::new(interface.clone());
+        let lms = LMS6002D::new(nios.clone());
+        let si5338 = SI5338::new(nios.clone());
+        let dac = DAC161S055::new(nios.clone());
+        let speed = detect_device_speed(&device);
 
         Ok(Box::new(BladeRf1 {
             device,
             interface,
+            nios,
             lms,
             si5338,
             dac,
+            dc_cal_rx: Mutex::new(None),
+            dc_cal_tx: Mutex::new(None),
+            fpga_version: OnceLock::new(),
+            fw_version: OnceLock::new(),
+            speed,
+            module_format: Mutex::new([None, None]),
+            xb: Mutex::new(ExpansionBoard::XbNone),
+            xb200: Mutex::new(None),
         }))
     }
 }
 
 impl BladeRf for BladeRf1 {}
+
+/* Derive the USB bus speed from the maximum packet size of the sample endpoints
+ * (512 bytes at High Speed, 1024 bytes at SuperSpeed). */
+fn detect_device_speed(device: &Device) -> BladerfDevSpeed {
+    let max_packet_size = device.active_configuration().ok().and_then(|config| {
+        config
+            .interface_alt_settings()
+            .find(|alt| alt.alternate_setting() == USB_IF_RF_LINK)
+            .and_then(|alt| alt.endpoints().map(|ep| ep.max_packet_size()).max())
+    });
+
+    match max_packet_size {
+        Some(1024) => BladerfDevSpeed::BladerfDeviceSpeedSuper,
+        Some(512) => BladerfDevSpeed::BladerfDeviceSpeedHigh,
+        _ => {
+            log::warn!("Unable to determine USB speed, assuming SuperSpeed");
+            BladerfDevSpeed::BladerfDeviceSpeedUnknown
+        }
+    }
+}
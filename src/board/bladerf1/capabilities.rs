@@ -0,0 +1,82 @@
+use crate::bladerf::BladerfVersion;
+
+/*
+ * Device capabilities are determined by the FPGA and FX3 firmware versions.
+ * Each bit of the capability mask describes a feature that is only available
+ * when the loaded FPGA image or firmware is recent enough.
+ */
+
+/**
+ * Capability bits for features that are FPGA version dependent
+ */
+pub const BLADERF_CAP_XB200: u64 = 1 << 1;
+pub const BLADERF_CAP_TIMESTAMPS: u64 = 1 << 2;
+pub const BLADERF_CAP_FPGA_TUNING: u64 = 1 << 3;
+pub const BLADERF_CAP_SCHEDULED_RETUNE: u64 = 1 << 4;
+pub const BLADERF_CAP_VCTCXO_TAMING_MODE: u64 = 1 << 5;
+pub const BLADERF_CAP_VCTCXO_TRIMDAC_READ: u64 = 1 << 6;
+pub const BLADERF_CAP_ATOMIC_NINT_NFRAC_WRITE: u64 = 1 << 7;
+pub const BLADERF_CAP_MASKED_XBIO_WRITE: u64 = 1 << 8;
+pub const BLADERF_CAP_QUICK_TUNE: u64 = 1 << 9;
+pub const BLADERF_CAP_TRX_SYNC_TRIG: u64 = 1 << 10;
+pub const BLADERF_CAP_AGC_DC_LUT: u64 = 1 << 11;
+pub const BLADERF_CAP_PKT_HANDLER_FMT: u64 = 1 << 12;
+
+/**
+ * Capability bits for features that are firmware version dependent
+ */
+pub const BLADERF_CAP_FW_LOOPBACK: u64 = 1 << 32;
+
+/**
+ * Minimum FPGA version required by each capability
+ */
+const FPGA_CAPABILITIES: &[(BladerfVersion, u64)] = &[
+    (BladerfVersion::new(0, 0, 5), BLADERF_CAP_XB200),
+    (BladerfVersion::new(0, 1, 0), BLADERF_CAP_TIMESTAMPS),
+    (BladerfVersion::new(0, 2, 0), BLADERF_CAP_FPGA_TUNING),
+    (BladerfVersion::new(0, 2, 0), BLADERF_CAP_SCHEDULED_RETUNE),
+    (BladerfVersion::new(0, 3, 0), BLADERF_CAP_PKT_HANDLER_FMT),
+    (BladerfVersion::new(0, 3, 2), BLADERF_CAP_VCTCXO_TAMING_MODE),
+    (
+        BladerfVersion::new(0, 4, 0),
+        BLADERF_CAP_ATOMIC_NINT_NFRAC_WRITE,
+    ),
+    (BladerfVersion::new(0, 4, 1), BLADERF_CAP_MASKED_XBIO_WRITE),
+    (
+        BladerfVersion::new(0, 5, 0),
+        BLADERF_CAP_VCTCXO_TRIMDAC_READ,
+    ),
+    (BladerfVersion::new(0, 6, 0), BLADERF_CAP_QUICK_TUNE),
+    (BladerfVersion::new(0, 6, 0), BLADERF_CAP_TRX_SYNC_TRIG),
+    (BladerfVersion::new(0, 7, 0), BLADERF_CAP_AGC_DC_LUT),
+];
+
+/**
+ * Minimum firmware version required by each capability
+ *
+ * Firmware loopback was fully implemented in FW v1.7.1 (1.7.0 could enable
+ * it, but 1.7.1 also allowed readback).
+ */
+const FW_CAPABILITIES: &[(BladerfVersion, u64)] =
+    &[(BladerfVersion::new(1, 7, 1), BLADERF_CAP_FW_LOOPBACK)];
+
+/// Capability mask supported by a given FPGA version.
+pub fn fpga_capabilities(fpga_version: &BladerfVersion) -> u64 {
+    FPGA_CAPABILITIES
+        .iter()
+        .filter(|(min_version, _)| fpga_version >= min_version)
+        .fold(0, |caps, (_, cap)| caps | cap)
+}
+
+/// Capability mask supported by a given firmware version.
+pub fn fw_capabilities(fw_version: &BladerfVersion) -> u64 {
+    FW_CAPABILITIES
+        .iter()
+        .filter(|(min_version, _)| fw_version >= min_version)
+        .fold(0, |caps, (_, cap)| caps | cap)
+}
+
+/// Check whether all bits of `cap` are present in `capabilities`.
+pub fn have_cap(capabilities: u64, cap: u64) -> bool {
+    (capabilities & cap) == cap
+}
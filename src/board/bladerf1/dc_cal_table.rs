@@ -0,0 +1,399 @@
+use crate::bladerf::{BLADERF_MODULE_RX, BLADERF_MODULE_TX};
+use crate::image::{BladerfImage, BladerfImageType};
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+/*
+ * DC calibration tables are bladeRF images (see [`BladerfImage`]) of type
+ * RX_DC_CAL or TX_DC_CAL, as written by `bladeRF-cli`. The image data is a
+ * little-endian table:
+ *
+ *  Length  Field
+ *       2  Magic (0x1ab1)
+ *       1  Table version (1 or 2)
+ *       4  Number of entries
+ *      10  LMS DC calibration register values (see [`DcCalRegVals`])
+ *
+ * followed by the entries, sorted by frequency:
+ *
+ *  Length  Field
+ *       4  Frequency (Hz)
+ *       2  LMS I correction
+ *       2  LMS Q correction
+ *      12  FPGA I/Q corrections for the max, mid and min AGC gains (version 2 only)
+ */
+const DC_CAL_TBL_MAGIC: u16 = 0x1ab1;
+const DC_CAL_TBL_META_SIZE: usize = 17;
+
+/* First table version with AGC correction values */
+pub const DC_CAL_TBL_VERSION_AGC: u8 = 2;
+
+const fn dc_cal_tbl_entry_size(version: u8) -> usize {
+    if version == 1 {
+        4 + 2 * 2
+    } else {
+        4 + 8 * 2
+    }
+}
+
+/**
+ * LMS6002D DC offset calibration register values stored alongside a table
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DcCalRegVals {
+    pub lpf_tuning: u8,
+    pub tx_lpf_i: u8,
+    pub tx_lpf_q: u8,
+    pub rx_lpf_i: u8,
+    pub rx_lpf_q: u8,
+    pub dc_ref: u8,
+    pub rxvga2a_i: u8,
+    pub rxvga2a_q: u8,
+    pub rxvga2b_i: u8,
+    pub rxvga2b_q: u8,
+}
+
+/**
+ * DC offset correction values for a single frequency
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DcCalEntry {
+    /* Frequency (Hz) this entry was calibrated at */
+    pub freq: u32,
+
+    /* LMS DC offset correction values */
+    pub dc_i: i16,
+    pub dc_q: i16,
+
+    /* Per gain-range correction values used by the FPGA AGC. Version 1
+     * tables don't have these, so they equal (dc_i, dc_q). */
+    pub max_dc_i: i16,
+    pub max_dc_q: i16,
+    pub mid_dc_i: i16,
+    pub mid_dc_q: i16,
+    pub min_dc_i: i16,
+    pub min_dc_q: i16,
+}
+
+/**
+ * Table of DC offset corrections over frequency, for either RX or TX
+ */
+#[derive(Clone, Debug)]
+pub struct DcCalTable {
+    channel: u8,
+    version: u8,
+    reg_vals: DcCalRegVals,
+    entries: Vec<DcCalEntry>,
+}
+
+impl DcCalTable {
+    /// Create a table with AGC correction values, as in a version 2 table.
+    pub fn new(channel: u8, reg_vals: DcCalRegVals, mut entries: Vec<DcCalEntry>) -> Result<Self> {
+        if channel != BLADERF_MODULE_RX && channel != BLADERF_MODULE_TX {
+            return Err(anyhow!("Invalid channel: {}", channel));
+        }
+        if entries.is_empty() {
+            return Err(anyhow!("DC calibration table contains no entries"));
+        }
+
+        entries.sort_by_key(|entry| entry.freq);
+        if entries.windows(2).any(|w| w[0].freq == w[1].freq) {
+            return Err(anyhow!(
+                "DC calibration table contains duplicate frequencies"
+            ));
+        }
+
+        Ok(Self {
+            channel,
+            version: DC_CAL_TBL_VERSION_AGC,
+            reg_vals,
+            entries,
+        })
+    }
+
+    /// Load a DC calibration table from a `.tbl` file written by `bladeRF-cli`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_image(&BladerfImage::load(path)?)
+    }
+
+    /// Get the DC calibration table contained in an RX_DC_CAL or TX_DC_CAL image.
+    pub fn from_image(image: &BladerfImage) -> Result<Self> {
+        let channel = match image.image_type {
+            BladerfImageType::RxDcCal => BLADERF_MODULE_RX,
+            BladerfImageType::TxDcCal => BLADERF_MODULE_TX,
+            image_type => {
+                return Err(anyhow!(
+                    "Image of type {:?} is not a DC calibration table",
+                    image_type
+                ))
+            }
+        };
+
+        let buf = &image.data;
+        if buf.len() < DC_CAL_TBL_META_SIZE {
+            return Err(anyhow!("DC calibration table too short"));
+        }
+
+        let magic = u16::from_le_bytes([buf[0], buf[1]]);
+        if magic != DC_CAL_TBL_MAGIC {
+            return Err(anyhow!(
+                "Invalid DC calibration table magic: {:#06x}",
+                magic
+            ));
+        }
+
+        let version = buf[2];
+        if version != 1 && version != 2 {
+            return Err(anyhow!(
+                "Unsupported DC calibration table version: {}",
+                version
+            ));
+        }
+
+        let n_entries = u32::from_le_bytes([buf[3], buf[4], buf[5], buf[6]]) as usize;
+        let entry_size = dc_cal_tbl_entry_size(version);
+        let expected = n_entries
+            .checked_mul(entry_size)
+            .and_then(|size| size.checked_add(DC_CAL_TBL_META_SIZE))
+            .ok_or_else(|| anyhow!("Invalid number of DC calibration entries"))?;
+        if buf.len() < expected {
+            return Err(anyhow!(
+                "DC calibration table truncated: expected {} bytes, got {}",
+                expected,
+                buf.len()
+            ));
+        }
+
+        let reg_vals = DcCalRegVals {
+            lpf_tuning: buf[7],
+            tx_lpf_i: buf[8],
+            tx_lpf_q: buf[9],
+            rx_lpf_i: buf[10],
+            rx_lpf_q: buf[11],
+            dc_ref: buf[12],
+            rxvga2a_i: buf[13],
+            rxvga2a_q: buf[14],
+            rxvga2b_i: buf[15],
+            rxvga2b_q: buf[16],
+        };
+
+        let entries = buf[DC_CAL_TBL_META_SIZE..expected]
+            .chunks_exact(entry_size)
+            .map(|entry| {
+                let value =
+                    |idx: usize| i16::from_le_bytes([entry[4 + 2 * idx], entry[5 + 2 * idx]]);
+                let (dc_i, dc_q) = (value(0), value(1));
+                let agc = if version >= DC_CAL_TBL_VERSION_AGC {
+                    [value(2), value(3), value(4), value(5), value(6), value(7)]
+                } else {
+                    [dc_i, dc_q, dc_i, dc_q, dc_i, dc_q]
+                };
+
+                DcCalEntry {
+                    freq: u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]),
+                    dc_i,
+                    dc_q,
+                    max_dc_i: agc[0],
+                    max_dc_q: agc[1],
+                    mid_dc_i: agc[2],
+                    mid_dc_q: agc[3],
+                    min_dc_i: agc[4],
+                    min_dc_q: agc[5],
+                }
+            })
+            .collect();
+
+        let mut table = Self::new(channel, reg_vals, entries)?;
+        table.version = version;
+        Ok(table)
+    }
+
+    /// Channel (`bladerf_channel_rx!(0)` or `bladerf_channel_tx!(0)`) the table applies to.
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Table format version. Only tables of version 2 and later contain AGC correction values.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// LMS DC calibration register values the table was generated with.
+    pub fn reg_vals(&self) -> DcCalRegVals {
+        self.reg_vals
+    }
+
+    /// Table entries, sorted by frequency.
+    pub fn entries(&self) -> &[DcCalEntry] {
+        &self.entries
+    }
+
+    /// Get the DC correction values for a frequency, linearly interpolating between the
+    /// two closest entries. Frequencies outside of the table use the nearest entry.
+    pub fn entry_for(&self, frequency: u32) -> DcCalEntry {
+        let idx = self
+            .entries
+            .partition_point(|entry| entry.freq <= frequency);
+
+        if idx == 0 {
+            return self.entries[0];
+        } else if idx == self.entries.len() {
+            return self.entries[idx - 1];
+        }
+
+        let low = &self.entries[idx - 1];
+        let high = &self.entries[idx];
+
+        let interpolate = |low_val: i16, high_val: i16| -> i16 {
+            let num = (frequency - low.freq) as i64 * (high_val as i64 - low_val as i64);
+            let den = (high.freq - low.freq) as i64;
+            (low_val as i64 + num / den) as i16
+        };
+
+        DcCalEntry {
+            freq: frequency,
+            dc_i: interpolate(low.dc_i, high.dc_i),
+            dc_q: interpolate(low.dc_q, high.dc_q),
+            max_dc_i: interpolate(low.max_dc_i, high.max_dc_i),
+            max_dc_q: interpolate(low.max_dc_q, high.max_dc_q),
+            mid_dc_i: interpolate(low.mid_dc_i, high.mid_dc_i),
+            mid_dc_q: interpolate(low.mid_dc_q, high.mid_dc_q),
+            min_dc_i: interpolate(low.min_dc_i, high.min_dc_i),
+            min_dc_q: interpolate(low.min_dc_q, high.min_dc_q),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(freq: u32, dc_i: i16, dc_q: i16, agc: [i16; 6]) -> DcCalEntry {
+        DcCalEntry {
+            freq,
+            dc_i,
+            dc_q,
+            max_dc_i: agc[0],
+            max_dc_q: agc[1],
+            mid_dc_i: agc[2],
+            mid_dc_q: agc[3],
+            min_dc_i: agc[4],
+            min_dc_q: agc[5],
+        }
+    }
+
+    fn table_data(version: u8, entries: &[DcCalEntry]) -> Vec<u8> {
+        let mut buf = DC_CAL_TBL_MAGIC.to_le_bytes().to_vec();
+        buf.push(version);
+        buf.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        for e in entries {
+            buf.extend_from_slice(&e.freq.to_le_bytes());
+            let mut values = vec![e.dc_i, e.dc_q];
+            if version >= 2 {
+                values.extend([
+                    e.max_dc_i, e.max_dc_q, e.mid_dc_i, e.mid_dc_q, e.min_dc_i, e.min_dc_q,
+                ]);
+            }
+            for value in values {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        buf
+    }
+
+    fn parse(image_type: BladerfImageType, data: Vec<u8>) -> Result<DcCalTable> {
+        let image = BladerfImage::new(image_type, 0, data);
+        DcCalTable::from_image(&BladerfImage::from_bytes(&image.to_bytes())?)
+    }
+
+    #[test]
+    fn parse_v2_table() {
+        /* Entries are stored out of order to check sorting */
+        let entries = [
+            entry(400_000_000, 10, -20, [1, 2, 3, 4, 5, 6]),
+            entry(300_000_000, -96, 32, [-96, 32, -64, 32, -64, 0]),
+        ];
+        let table = parse(BladerfImageType::RxDcCal, table_data(2, &entries)).unwrap();
+
+        assert_eq!(table.channel(), BLADERF_MODULE_RX);
+        assert_eq!(table.version(), 2);
+        assert_eq!(table.entries(), &[entries[1], entries[0]]);
+        assert_eq!(
+            table.reg_vals(),
+            DcCalRegVals {
+                lpf_tuning: 1,
+                tx_lpf_i: 2,
+                tx_lpf_q: 3,
+                rx_lpf_i: 4,
+                rx_lpf_q: 5,
+                dc_ref: 6,
+                rxvga2a_i: 7,
+                rxvga2a_q: 8,
+                rxvga2b_i: 9,
+                rxvga2b_q: 10,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_v1_table() {
+        let entries = [entry(300_000_000, -5, 7, [0; 6])];
+        let table = parse(BladerfImageType::TxDcCal, table_data(1, &entries)).unwrap();
+
+        assert_eq!(table.channel(), BLADERF_MODULE_TX);
+        assert_eq!(table.version(), 1);
+        assert_eq!(
+            table.entries(),
+            &[entry(300_000_000, -5, 7, [-5, 7, -5, 7, -5, 7])]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid_tables() {
+        let entries = [entry(300_000_000, 1, 2, [0; 6])];
+        let data = table_data(2, &entries);
+
+        assert!(parse(BladerfImageType::Raw, data.clone()).is_err());
+        assert!(parse(BladerfImageType::RxDcCal, data[..data.len() - 1].to_vec()).is_err());
+        assert!(parse(BladerfImageType::RxDcCal, table_data(2, &[])).is_err());
+
+        let mut bad_magic = data.clone();
+        bad_magic[0] ^= 0xff;
+        assert!(parse(BladerfImageType::RxDcCal, bad_magic).is_err());
+
+        let mut bad_version = data;
+        bad_version[2] = 3;
+        assert!(parse(BladerfImageType::RxDcCal, bad_version).is_err());
+
+        let duplicate = [entries[0], entries[0]];
+        assert!(parse(BladerfImageType::RxDcCal, table_data(2, &duplicate)).is_err());
+    }
+
+    #[test]
+    fn entry_for_interpolates() {
+        let table = DcCalTable::new(
+            BLADERF_MODULE_RX,
+            DcCalRegVals::default(),
+            vec![
+                entry(300_000_000, -100, 40, [0, 0, 10, -10, 100, -100]),
+                entry(400_000_000, 100, -40, [0, 10, 20, -20, 200, -200]),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            table.entry_for(350_000_000),
+            entry(350_000_000, 0, 0, [0, 5, 15, -15, 150, -150])
+        );
+        assert_eq!(
+            table.entry_for(325_000_000),
+            entry(325_000_000, -50, 20, [0, 2, 12, -12, 125, -125])
+        );
+
+        /* Exact matches and frequencies outside of the table use the entry as-is */
+        assert_eq!(table.entry_for(400_000_000), table.entries()[1]);
+        assert_eq!(table.entry_for(100_000_000), table.entries()[0]);
+        assert_eq!(table.entry_for(3_800_000_000), table.entries()[1]);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::board::bladerf1::capabilities::{
+    have_cap, BLADERF_CAP_MASKED_XBIO_WRITE, BLADERF_CAP_XB200,
+};
+use crate::board::bladerf1::BladeRf1;
+use crate::nios::constants::{NIOS_PKT_32X32_TARGET_EXP, NIOS_PKT_32X32_TARGET_EXP_DIR};
+use anyhow::{anyhow, Result};
+
+/**
+ * Expansion boards
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpansionBoard {
+    /**< No expansion boards attached */
+    XbNone = 0,
+    /**< XB-100 GPIO expansion board. */
+    Xb100,
+    /**< XB-200 Transverter board */
+    Xb200,
+    /**< XB-300 Amplifier board */
+    Xb300,
+}
+
+impl BladeRf1 {
+    /// Attach and initialize an expansion board. Switching between board types is not
+    /// supported; attaching the board that is already attached is a no-op.
+    pub fn expansion_attach(&self, xb: ExpansionBoard) -> Result<()> {
+        let attached = self.expansion_get_attached();
+
+        if xb == attached {
+            return Ok(());
+        } else if attached != ExpansionBoard::XbNone {
+            return Err(anyhow!("Switching XB types is not supported"));
+        }
+
+        match xb {
+            ExpansionBoard::Xb100 => {
+                if !have_cap(self.capabilities()?, BLADERF_CAP_MASKED_XBIO_WRITE) {
+                    return Err(anyhow!("XB100 support requires FPGA v0.4.1 or later"));
+                }
+
+                self.xb100_init()?;
+            }
+            ExpansionBoard::Xb200 => {
+                if !have_cap(self.capabilities()?, BLADERF_CAP_XB200) {
+                    return Err(anyhow!("XB200 support requires FPGA v0.0.5 or later"));
+                }
+
+                self.xb200_attach()?;
+                self.xb200_enable(true)?;
+                self.xb200_init()?;
+            }
+            ExpansionBoard::Xb300 => {
+                self.xb300_attach()?;
+                self.xb300_enable(true)?;
+                self.xb300_init()?;
+            }
+            ExpansionBoard::XbNone => {
+                return Err(anyhow!("Disabling an attached XB is not supported"));
+            }
+        }
+
+        *self.xb.lock().unwrap() = xb;
+        Ok(())
+    }
+
+    /// Get the expansion board attached with [`BladeRf1::expansion_attach`].
+    pub fn expansion_get_attached(&self) -> ExpansionBoard {
+        *self.xb.lock().unwrap()
+    }
+
+    /// Read the expansion header GPIO pins.
+    pub fn expansion_gpio_read(&self) -> Result<u32> {
+        self.nios_32x32_masked_read(NIOS_PKT_32X32_TARGET_EXP, 0xffffffff)
+    }
+
+    /// Write all expansion header GPIO pins configured as outputs.
+    pub fn expansion_gpio_write(&self, val: u32) -> Result<()> {
+        self.nios_32x32_masked_write(NIOS_PKT_32X32_TARGET_EXP, 0xffffffff, val)
+    }
+
+    /// Write only the expansion header GPIO pins selected by `mask`.
+    pub fn expansion_gpio_masked_write(&self, mask: u32, val: u32) -> Result<()> {
+        self.expansion_masked_write(NIOS_PKT_32X32_TARGET_EXP, mask, val)
+    }
+
+    /// Read the expansion header GPIO direction register. A set bit denotes an output.
+    pub fn expansion_gpio_dir_read(&self) -> Result<u32> {
+        self.nios_32x32_masked_read(NIOS_PKT_32X32_TARGET_EXP_DIR, 0xffffffff)
+    }
+
+    /// Configure the direction of all expansion header GPIO pins. A set bit denotes an output.
+    pub fn expansion_gpio_dir_write(&self, outputs: u32) -> Result<()> {
+        self.nios_32x32_masked_write(NIOS_PKT_32X32_TARGET_EXP_DIR, 0xffffffff, outputs)
+    }
+
+    /// Configure the direction of only the expansion header GPIO pins selected by `mask`.
+    pub fn expansion_gpio_dir_masked_write(&self, mask: u32, outputs: u32) -> Result<()> {
+        self.expansion_masked_write(NIOS_PKT_32X32_TARGET_EXP_DIR, mask, outputs)
+    }
+
+    fn expansion_masked_write(&self, target_id: u8, mask: u32, val: u32) -> Result<()> {
+        if have_cap(self.capabilities()?, BLADERF_CAP_MASKED_XBIO_WRITE) {
+            return self.nios_32x32_masked_write(target_id, mask, val);
+        }
+
+        /* Older FPGAs ignore the mask, so read-modify-write instead */
+        let mut tmp = self.nios_32x32_masked_read(target_id, 0xffffffff)?;
+        tmp &= !mask;
+        tmp |= val & mask;
+        self.nios_32x32_masked_write(target_id, 0xffffffff, tmp)
+    }
+}
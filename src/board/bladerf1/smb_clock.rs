@@ -0,0 +1,195 @@
+use crate::bladerf::{BladerfRationalRate, BladerfSmbMode};
+use crate::board::bladerf1::expansion::ExpansionBoard;
+use crate::board::bladerf1::{BladeRf1, BLADERF_SMB_FREQUENCY_MAX, BLADERF_SMB_FREQUENCY_MIN};
+use anyhow::{anyhow, Result};
+
+struct RegVal {
+    addr: u8,
+    data: u8,
+}
+
+/* Power-on defaults with SMB clock port not in use */
+const DEFAULT_CONFIG: [RegVal; 16] = [
+    RegVal {
+        addr: 6,
+        data: 0x08,
+    },
+    RegVal {
+        addr: 28,
+        data: 0x0b,
+    },
+    RegVal {
+        addr: 29,
+        data: 0x08,
+    },
+    RegVal {
+        addr: 30,
+        data: 0xb0,
+    },
+    RegVal {
+        addr: 34,
+        data: 0xe3,
+    },
+    RegVal {
+        addr: 39,
+        data: 0x00,
+    },
+    /* Reset Multisynth 3 */
+    RegVal {
+        addr: 86,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 87,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 88,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 89,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 90,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 91,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 92,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 93,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 94,
+        data: 0x00,
+    },
+    RegVal {
+        addr: 95,
+        data: 0x00,
+    },
+];
+
+const INPUT_CONFIG: [RegVal; 4] = [
+    RegVal {
+        addr: 6,
+        data: 0x04,
+    },
+    RegVal {
+        addr: 28,
+        data: 0x2b,
+    },
+    RegVal {
+        addr: 29,
+        data: 0x28,
+    },
+    RegVal {
+        addr: 30,
+        data: 0xa8,
+    },
+];
+
+const OUTPUT_CONFIG: [RegVal; 1] = [RegVal {
+    addr: 34,
+    data: 0x22,
+}];
+
+impl BladeRf1 {
+    fn smb_write_regs(&self, regs: &[RegVal]) -> Result<()> {
+        for reg in regs {
+            self.si5338.write(reg.addr, reg.data)?;
+        }
+        Ok(())
+    }
+
+    fn smb_check_regs(&self, regs: &[RegVal]) -> Result<bool> {
+        for reg in regs {
+            if self.si5338.read(reg.addr)? != reg.data {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Configure the SMB connector as a reference clock output, input, or leave it unused.
+    pub fn set_smb_mode(&self, mode: BladerfSmbMode) -> Result<()> {
+        if self.expansion_get_attached() == ExpansionBoard::Xb200 {
+            return Err(anyhow!("Cannot use SMB port when XB-200 is attached"));
+        }
+
+        /* Reset initial state */
+        self.smb_write_regs(&DEFAULT_CONFIG)?;
+
+        /* Apply changes */
+        match mode {
+            BladerfSmbMode::BladerfSmbModeDisabled => Ok(()),
+            BladerfSmbMode::BladerfSmbModeOutput => self.smb_write_regs(&OUTPUT_CONFIG),
+            BladerfSmbMode::BladerfSmbModeInput => self.smb_write_regs(&INPUT_CONFIG),
+        }
+    }
+
+    pub fn get_smb_mode(&self) -> Result<BladerfSmbMode> {
+        if self.smb_check_regs(&INPUT_CONFIG)? {
+            return Ok(BladerfSmbMode::BladerfSmbModeInput);
+        }
+
+        if self.smb_check_regs(&OUTPUT_CONFIG)? {
+            return Ok(BladerfSmbMode::BladerfSmbModeOutput);
+        }
+
+        /* Only the first few registers distinguish the modes; Multisynth 3
+         * is reprogrammed by set_smb_frequency() */
+        if self.smb_check_regs(&DEFAULT_CONFIG[..6])? {
+            return Ok(BladerfSmbMode::BladerfSmbModeDisabled);
+        }
+
+        Err(anyhow!(
+            "Unexpected SI5338 configuration for SMB clock port"
+        ))
+    }
+
+    /// Set the frequency of the clock output on the SMB connector, in Hz.
+    /// Returns the actual frequency set.
+    pub fn set_smb_frequency(&self, frequency: u32) -> Result<u32> {
+        if !(BLADERF_SMB_FREQUENCY_MIN..=BLADERF_SMB_FREQUENCY_MAX).contains(&frequency) {
+            return Err(anyhow!(
+                "SMB frequency {} Hz is outside of the supported range [{}, {}]",
+                frequency,
+                BLADERF_SMB_FREQUENCY_MIN,
+                BLADERF_SMB_FREQUENCY_MAX
+            ));
+        }
+
+        self.si5338.set_smb_freq(frequency)
+    }
+
+    /// Set the frequency of the clock output on the SMB connector as a rational value.
+    /// Returns the actual frequency set.
+    pub fn set_rational_smb_frequency(
+        &self,
+        frequency: &BladerfRationalRate,
+    ) -> Result<BladerfRationalRate> {
+        if frequency.den == 0 {
+            return Err(anyhow!(
+                "Rational SMB frequency denominator must be non-zero"
+            ));
+        }
+
+        self.si5338.set_rational_smb_freq(frequency)
+    }
+
+    pub fn get_smb_frequency(&self) -> Result<u32> {
+        self.si5338.get_smb_freq()
+    }
+
+    pub fn get_rational_smb_frequency(&self) -> Result<BladerfRationalRate> {
+        self.si5338.get_rational_smb_freq()
+    }
+}
@@ -0,0 +1,745 @@
+use crate::bladerf::{
+    BladerfDevSpeed, BladerfDirection, BladerfFormat, BladerfMetadata, BLADERF_META_FLAG_RX_NOW,
+    BLADERF_META_FLAG_TX_BURST_END, BLADERF_META_FLAG_TX_BURST_START, BLADERF_META_FLAG_TX_NOW,
+    BLADERF_META_FLAG_TX_UPDATE_TIMESTAMP, BLADERF_META_STATUS_OVERRUN, BLADERF_MODULE_RX,
+    BLADERF_MODULE_TX,
+};
+use crate::board::bladerf1::BladeRf1;
+use anyhow::{anyhow, Result};
+use futures_lite::future::block_on;
+use nusb::transfer::{Queue, RequestBuffer};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/* Sample endpoints of the RF link interface */
+const ENDPOINT_RX_SAMPLES: u8 = 0x81;
+const ENDPOINT_TX_SAMPLES: u8 = 0x01;
+
+/* Size of one SC16Q11 sample (16-bit I and Q) in bytes */
+const SAMPLE_SIZE: usize = 4;
+
+/* With metadata enabled, the FPGA exchanges samples in fixed-size messages,
+ * each starting with a 16 byte header. Messages are 2048 bytes at SuperSpeed,
+ * and 1024 bytes at High Speed, where the FPGA uses smaller DMA transfers. */
+const METADATA_HEADER_SIZE: usize = 16;
+const METADATA_TIMESTAMP_OFFSET: usize = 4;
+const METADATA_FLAGS_OFFSET: usize = 12;
+
+fn metadata_message_size(speed: BladerfDevSpeed) -> usize {
+    /* An unknown speed is treated as SuperSpeed, as in config_gpio_write() */
+    match speed {
+        BladerfDevSpeed::BladerfDeviceSpeedHigh => 1024,
+        _ => 2048,
+    }
+}
+
+/**
+ * Stream buffer configuration
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct StreamConfig {
+    /** Number of USB transfers kept in flight */
+    pub num_transfers: usize,
+    /** Size of each transfer in samples. Must be a multiple of 1024. */
+    pub buffer_size: usize,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            num_transfers: 8,
+            buffer_size: 8192,
+        }
+    }
+}
+
+impl StreamConfig {
+    fn buffer_bytes(&self) -> Result<usize> {
+        if self.num_transfers == 0 {
+            return Err(anyhow!("At least one transfer is required"));
+        }
+        if self.buffer_size == 0 || !self.buffer_size.is_multiple_of(1024) {
+            return Err(anyhow!(
+                "Buffer size must be a multiple of 1024 samples, got {}",
+                self.buffer_size
+            ));
+        }
+        Ok(self.buffer_size * SAMPLE_SIZE)
+    }
+}
+
+fn metadata_get(header: &[u8]) -> (u64, u32) {
+    let timestamp = u64::from_le_bytes(
+        header[METADATA_TIMESTAMP_OFFSET..METADATA_TIMESTAMP_OFFSET + 8]
+            .try_into()
+            .unwrap(),
+    );
+    let flags = u32::from_le_bytes(
+        header[METADATA_FLAGS_OFFSET..METADATA_FLAGS_OFFSET + 4]
+            .try_into()
+            .unwrap(),
+    );
+    (timestamp, flags)
+}
+
+fn metadata_push(buf: &mut Vec<u8>, timestamp: u64, flags: u32) {
+    buf.extend_from_slice(&[0u8; METADATA_TIMESTAMP_OFFSET]);
+    buf.extend_from_slice(&timestamp.to_le_bytes());
+    buf.extend_from_slice(&flags.to_le_bytes());
+}
+
+/**
+ * Cumulative RX stream statistics
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StreamStats {
+    /** Samples delivered to the caller */
+    pub samples_received: u64,
+    /** Number of detected discontinuities in the sample stream */
+    pub overruns: u64,
+    /** Total number of samples lost over all overruns. Discontinuities where
+     *  the counter or timestamp went backwards do not contribute. */
+    pub samples_dropped: u64,
+    /** Number of samples lost in the most recent overrun, 0 if unknown */
+    pub last_dropped: u64,
+}
+
+/**
+ * Result of a counter mode throughput validation
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ThroughputReport {
+    /** Time spent streaming */
+    pub elapsed: Duration,
+    /** Achieved sample rate in samples per second */
+    pub samples_per_second: f64,
+    /** Achieved USB throughput in bytes per second */
+    pub bytes_per_second: f64,
+    /** Received samples and detected gaps in the counter */
+    pub stats: StreamStats,
+}
+
+/// Receive stream. The RX module is enabled while the streamer exists.
+pub struct RxStreamer<'a> {
+    dev: &'a BladeRf1,
+    format: BladerfFormat,
+    queue: Queue<RequestBuffer>,
+    buffer_bytes: usize,
+    /* Size of a metadata message in bytes */
+    message_size: usize,
+    /* Most recently completed transfer and read position within it */
+    buf: Vec<u8>,
+    pos: usize,
+    /* Timestamp of the current message, and the one expected for the next */
+    msg_timestamp: u64,
+    next_msg_timestamp: Option<u64>,
+    /* Counter mode checking (raw format only) */
+    check_counter: bool,
+    next_counter: Option<u32>,
+    stats: StreamStats,
+}
+
+impl<'a> RxStreamer<'a> {
+    pub fn format(&self) -> BladerfFormat {
+        self.format
+    }
+
+    /// Cumulative overrun and drop statistics of this stream.
+    pub fn stats(&self) -> StreamStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = StreamStats::default();
+    }
+
+    /// Check the 32-bit counter the FPGA sends instead of samples in counter mode for gaps,
+    /// accounting them as overruns. Only applies to [`BladerfFormat::BladerfFormatSc16Q11`].
+    pub fn set_counter_check(&mut self, enable: bool) {
+        self.check_counter = enable;
+        self.next_counter = None;
+    }
+
+    /* A counter or timestamp going backwards is a discontinuity that does not
+     * tell how many samples were lost, reported with `dropped` set to None */
+    fn record_overrun(&mut self, timestamp: u64, dropped: Option<u64>) {
+        self.stats.overruns += 1;
+        self.stats.samples_dropped += dropped.unwrap_or(0);
+        self.stats.last_dropped = dropped.unwrap_or(0);
+        match dropped {
+            Some(dropped) => {
+                log::debug!("RX overrun at {}: {} samples dropped", timestamp, dropped)
+            }
+            None => log::debug!("RX discontinuity at {}", timestamp),
+        }
+    }
+
+    /* Verify the counter values of `count` samples at the read position */
+    fn check_counter_values(&mut self, count: usize) {
+        let start = self.pos;
+        for i in 0..count {
+            let offset = start + i * SAMPLE_SIZE;
+            let counter = u32::from_le_bytes(self.buf[offset..offset + 4].try_into().unwrap());
+
+            if let Some(expected) = self.next_counter {
+                if counter != expected {
+                    self.record_overrun(
+                        counter as u64,
+                        counter.checked_sub(expected).map(u64::from),
+                    );
+                }
+            }
+            self.next_counter = Some(counter.wrapping_add(1));
+        }
+    }
+
+    fn next_transfer(&mut self) -> Result<()> {
+        let data = block_on(self.queue.next_complete()).into_result()?;
+
+        /* A short transfer must still hold whole samples, or whole messages with metadata */
+        let unit = match self.format {
+            BladerfFormat::BladerfFormatSc16Q11Meta => self.message_size,
+            _ => SAMPLE_SIZE,
+        };
+        if !data.len().is_multiple_of(unit) {
+            let len = data.len();
+            self.queue
+                .submit(RequestBuffer::reuse(data, self.buffer_bytes));
+            return Err(anyhow!(
+                "Received {} bytes, not a multiple of {} bytes",
+                len,
+                unit
+            ));
+        }
+
+        let consumed = std::mem::replace(&mut self.buf, data);
+        self.queue
+            .submit(RequestBuffer::reuse(consumed, self.buffer_bytes));
+        self.pos = 0;
+        Ok(())
+    }
+
+    fn copy_samples(&mut self, samples: &mut [i16], count: usize) {
+        let bytes = &self.buf[self.pos..self.pos + count * SAMPLE_SIZE];
+        for (sample, raw) in samples.iter_mut().zip(bytes.chunks_exact(2)) {
+            *sample = i16::from_le_bytes([raw[0], raw[1]]);
+        }
+        self.pos += count * SAMPLE_SIZE;
+        self.stats.samples_received += count as u64;
+    }
+
+    /// Receive interleaved I/Q samples into `samples`.
+    ///
+    /// Without metadata, the buffer is always filled completely. With metadata (requires
+    /// [`BladerfFormat::BladerfFormatSc16Q11Meta`]), reception starts at `metadata.timestamp`
+    /// unless [`BLADERF_META_FLAG_RX_NOW`] is set. On return, `metadata.timestamp` holds the
+    /// timestamp of the first sample. If samples were lost, [`BLADERF_META_STATUS_OVERRUN`] is
+    /// set and `metadata.actual_count` reports the number of contiguous samples received.
+    /// Overruns and the number of lost samples are accumulated in [`RxStreamer::stats`].
+    pub fn read(
+        &mut self,
+        samples: &mut [i16],
+        metadata: Option<&mut BladerfMetadata>,
+    ) -> Result<()> {
+        if !samples.len().is_multiple_of(2) {
+            return Err(anyhow!("Sample buffer must hold interleaved I/Q pairs"));
+        }
+
+        match (self.format, metadata) {
+            (BladerfFormat::BladerfFormatSc16Q11, None) => self.read_raw(samples),
+            (BladerfFormat::BladerfFormatSc16Q11Meta, Some(metadata)) => {
+                self.read_meta(samples, metadata)
+            }
+            (BladerfFormat::BladerfFormatSc16Q11Meta, None) => {
+                let mut metadata = BladerfMetadata {
+                    flags: BLADERF_META_FLAG_RX_NOW,
+                    ..Default::default()
+                };
+                let mut count = 0;
+                while count < samples.len() / 2 {
+                    self.read_meta(&mut samples[count * 2..], &mut metadata)?;
+                    count += metadata.actual_count;
+                }
+                Ok(())
+            }
+            (BladerfFormat::BladerfFormatSc16Q11, Some(_)) => Err(anyhow!(
+                "Metadata requires the BladerfFormatSc16Q11Meta format"
+            )),
+        }
+    }
+
+    fn read_raw(&mut self, samples: &mut [i16]) -> Result<()> {
+        let num_samples = samples.len() / 2;
+        let mut count = 0;
+
+        while count < num_samples {
+            if self.pos >= self.buf.len() {
+                self.next_transfer()?;
+                continue;
+            }
+
+            let available = (self.buf.len() - self.pos) / SAMPLE_SIZE;
+            let n = available.min(num_samples - count);
+            if self.check_counter {
+                self.check_counter_values(n);
+            }
+            self.copy_samples(&mut samples[count * 2..], n);
+            count += n;
+        }
+        Ok(())
+    }
+
+    fn read_meta(&mut self, samples: &mut [i16], metadata: &mut BladerfMetadata) -> Result<()> {
+        let rx_now = (metadata.flags & BLADERF_META_FLAG_RX_NOW) != 0;
+        let num_samples = samples.len() / 2;
+        let samples_per_message = (self.message_size - METADATA_HEADER_SIZE) / SAMPLE_SIZE;
+        let mut count = 0;
+
+        metadata.status = 0;
+        metadata.actual_count = 0;
+
+        while count < num_samples {
+            if self.pos >= self.buf.len() {
+                self.next_transfer()?;
+                continue;
+            }
+
+            if self.pos.is_multiple_of(self.message_size) {
+                let (timestamp, _flags) = metadata_get(&self.buf[self.pos..]);
+
+                if let Some(expected) = self.next_msg_timestamp.filter(|&e| e != timestamp) {
+                    self.record_overrun(timestamp, timestamp.checked_sub(expected));
+                    self.next_msg_timestamp = None;
+                    metadata.status |= BLADERF_META_STATUS_OVERRUN;
+
+                    /* Return the contiguous samples received so far. The
+                     * discontinuous message is handled by the next call. */
+                    if count > 0 {
+                        break;
+                    }
+                }
+
+                self.msg_timestamp = timestamp;
+                self.next_msg_timestamp = Some(timestamp + samples_per_message as u64);
+                self.pos += METADATA_HEADER_SIZE;
+            }
+
+            let offset = (self.pos % self.message_size - METADATA_HEADER_SIZE) / SAMPLE_SIZE;
+            let timestamp = self.msg_timestamp + offset as u64;
+            let available =
+                (samples_per_message - offset).min((self.buf.len() - self.pos) / SAMPLE_SIZE);
+
+            if count == 0 && !rx_now {
+                if timestamp > metadata.timestamp {
+                    return Err(anyhow!(
+                        "Requested timestamp {} is in the past (current: {})",
+                        metadata.timestamp,
+                        timestamp
+                    ));
+                }
+
+                /* Discard samples until the requested timestamp is reached */
+                let skip = (metadata.timestamp - timestamp).min(available as u64) as usize;
+                if skip > 0 {
+                    self.pos += skip * SAMPLE_SIZE;
+                    continue;
+                }
+            }
+
+            if count == 0 {
+                metadata.timestamp = timestamp;
+            }
+
+            let n = available.min(num_samples - count);
+            self.copy_samples(&mut samples[count * 2..], n);
+            count += n;
+        }
+
+        metadata.actual_count = count;
+        Ok(())
+    }
+}
+
+impl Drop for RxStreamer<'_> {
+    fn drop(&mut self) {
+        self.queue.cancel_all();
+        while self.queue.pending() > 0 {
+            let _ = block_on(self.queue.next_complete());
+        }
+        let _ = self.dev.enable_module(BLADERF_MODULE_RX, false);
+    }
+}
+
+/**
+ * Events reported for timestamped TX transfers
+ *
+ * The FPGA does not report the status of TX messages back to the host. When a
+ * transfer completes, its first timestamp is compared against the FPGA TX
+ * timestamp to find transfers that reached the FPGA after they were due.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxEvent {
+    /**
+     * The start of a burst reached the FPGA after its timestamp had passed.
+     */
+    Late {
+        /** Requested start of the burst */
+        timestamp: u64,
+        /** FPGA TX timestamp when the transfer completed */
+        current: u64,
+    },
+    /**
+     * Samples continuing a burst reached the FPGA after their timestamp had
+     * passed, leaving a gap in the burst.
+     */
+    Underrun {
+        /** Timestamp of the first sample of the transfer */
+        timestamp: u64,
+        /** FPGA TX timestamp when the transfer completed */
+        current: u64,
+    },
+}
+
+/* Timestamp of the first message of a TX transfer */
+#[derive(Clone, Copy, Debug)]
+enum TxTransfer {
+    BurstStart(u64),
+    Continuation(u64),
+    /* Start of a burst sent with BLADERF_META_FLAG_TX_NOW */
+    Untimed,
+}
+
+/// Transmit stream. The TX module is enabled while the streamer exists.
+pub struct TxStreamer<'a> {
+    dev: &'a BladeRf1,
+    format: BladerfFormat,
+    queue: Queue<Vec<u8>>,
+    num_transfers: usize,
+    buffer_bytes: usize,
+    /* Size of a metadata message in bytes */
+    message_size: usize,
+    /* Buffer currently being filled */
+    buf: Vec<u8>,
+    /* Burst state and timestamp of the next sample (metadata only) */
+    in_burst: bool,
+    timestamp: u64,
+    /* Timestamp information of `buf`, and of each submitted transfer in order */
+    buf_transfer: Option<TxTransfer>,
+    submitted: VecDeque<Option<TxTransfer>>,
+    events: VecDeque<TxEvent>,
+}
+
+impl<'a> TxStreamer<'a> {
+    pub fn format(&self) -> BladerfFormat {
+        self.format
+    }
+
+    fn submit(&mut self) -> Result<()> {
+        let buf = std::mem::take(&mut self.buf);
+        self.queue.submit(buf);
+        self.submitted.push_back(self.buf_transfer.take());
+
+        if self.queue.pending() >= self.num_transfers {
+            self.buf = self.reap()?;
+            self.buf.clear();
+        } else {
+            self.buf = Vec::with_capacity(self.buffer_bytes);
+        }
+        Ok(())
+    }
+
+    /* Wait for the oldest transfer to complete, and check whether it was late */
+    fn reap(&mut self) -> Result<Vec<u8>> {
+        let transfer = self.submitted.pop_front().flatten();
+        let buf = block_on(self.queue.next_complete()).into_result()?.reuse();
+
+        let (timestamp, burst_start) = match transfer {
+            Some(TxTransfer::BurstStart(timestamp)) => (timestamp, true),
+            Some(TxTransfer::Continuation(timestamp)) => (timestamp, false),
+            Some(TxTransfer::Untimed) | None => return Ok(buf),
+        };
+
+        let current = self.dev.get_timestamp(BladerfDirection::BladerfTx)?;
+        if timestamp <= current {
+            self.events.push_back(if burst_start {
+                TxEvent::Late { timestamp, current }
+            } else {
+                TxEvent::Underrun { timestamp, current }
+            });
+        }
+        Ok(buf)
+    }
+
+    /// Transmit a complete burst starting at `at_timestamp`. Requires
+    /// [`BladerfFormat::BladerfFormatSc16Q11Meta`].
+    ///
+    /// The burst is followed by zeros up to the end of its last message, so the DAC returns to
+    /// zero after the burst. Bursts that reach the FPGA after `at_timestamp`, or with gaps, are
+    /// reported as [`TxEvent::Late`] and [`TxEvent::Underrun`] through [`TxStreamer::events`]
+    /// once their transfers complete.
+    pub fn send_burst(&mut self, samples: &[i16], at_timestamp: u64) -> Result<()> {
+        if self.format != BladerfFormat::BladerfFormatSc16Q11Meta {
+            return Err(anyhow!(
+                "Bursts require the BladerfFormatSc16Q11Meta format"
+            ));
+        }
+
+        self.write_meta(
+            samples,
+            &BladerfMetadata {
+                timestamp: at_timestamp,
+                flags: BLADERF_META_FLAG_TX_BURST_START | BLADERF_META_FLAG_TX_BURST_END,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Wait until all submitted transfers have completed.
+    pub fn wait_complete(&mut self) -> Result<()> {
+        while self.queue.pending() > 0 {
+            self.reap()?;
+        }
+        Ok(())
+    }
+
+    /// Take all events reported so far.
+    pub fn events(&mut self) -> impl Iterator<Item = TxEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    fn push_samples(&mut self, samples: &[i16]) {
+        for sample in samples {
+            self.buf.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+
+    /* Zero-pad the buffer up to the next message boundary */
+    fn pad_message(&mut self) -> Result<()> {
+        let partial = self.buf.len() % self.message_size;
+        if partial != 0 {
+            let padding = self.message_size - partial;
+            self.buf.resize(self.buf.len() + padding, 0);
+            self.timestamp += (padding / SAMPLE_SIZE) as u64;
+        }
+
+        if self.buf.len() >= self.buffer_bytes {
+            self.submit()?;
+        }
+        Ok(())
+    }
+
+    /// Transmit interleaved I/Q samples.
+    ///
+    /// With [`BladerfFormat::BladerfFormatSc16Q11Meta`], `metadata` is required and controls
+    /// bursts: [`BLADERF_META_FLAG_TX_BURST_START`] starts a burst at `metadata.timestamp`
+    /// (or as soon as possible with [`BLADERF_META_FLAG_TX_NOW`]), and
+    /// [`BLADERF_META_FLAG_TX_BURST_END`] zero-pads and flushes the burst.
+    pub fn write(&mut self, samples: &[i16], metadata: Option<&BladerfMetadata>) -> Result<()> {
+        if !samples.len().is_multiple_of(2) {
+            return Err(anyhow!("Sample buffer must hold interleaved I/Q pairs"));
+        }
+
+        match (self.format, metadata) {
+            (BladerfFormat::BladerfFormatSc16Q11, None) => self.write_raw(samples),
+            (BladerfFormat::BladerfFormatSc16Q11Meta, Some(metadata)) => {
+                self.write_meta(samples, metadata)
+            }
+            (BladerfFormat::BladerfFormatSc16Q11Meta, None) => Err(anyhow!(
+                "The BladerfFormatSc16Q11Meta format requires metadata"
+            )),
+            (BladerfFormat::BladerfFormatSc16Q11, Some(_)) => Err(anyhow!(
+                "Metadata requires the BladerfFormatSc16Q11Meta format"
+            )),
+        }
+    }
+
+    fn write_raw(&mut self, mut samples: &[i16]) -> Result<()> {
+        while !samples.is_empty() {
+            let space = (self.buffer_bytes - self.buf.len()) / 2;
+            let n = space.min(samples.len());
+            self.push_samples(&samples[..n]);
+            samples = &samples[n..];
+
+            if self.buf.len() >= self.buffer_bytes {
+                self.submit()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_meta(&mut self, mut samples: &[i16], metadata: &BladerfMetadata) -> Result<()> {
+        if (metadata.flags & BLADERF_META_FLAG_TX_BURST_START) != 0 {
+            if self.in_burst {
+                return Err(anyhow!("TX burst started while another burst is active"));
+            }
+
+            if (metadata.flags & BLADERF_META_FLAG_TX_NOW) != 0 {
+                self.timestamp = self.dev.get_timestamp(BladerfDirection::BladerfTx)?;
+                self.buf_transfer = Some(TxTransfer::Untimed);
+            } else {
+                self.timestamp = metadata.timestamp;
+                self.buf_transfer = Some(TxTransfer::BurstStart(metadata.timestamp));
+            }
+            self.in_burst = true;
+        } else if !self.in_burst {
+            return Err(anyhow!(
+                "TX burst not started, set BLADERF_META_FLAG_TX_BURST_START"
+            ));
+        } else if (metadata.flags & BLADERF_META_FLAG_TX_UPDATE_TIMESTAMP) != 0 {
+            self.pad_message()?;
+            self.timestamp = metadata.timestamp;
+        }
+
+        while !samples.is_empty() {
+            if self.buf.len().is_multiple_of(self.message_size) {
+                if self.buf_transfer.is_none() {
+                    self.buf_transfer = Some(TxTransfer::Continuation(self.timestamp));
+                }
+                metadata_push(&mut self.buf, self.timestamp, 0);
+            }
+
+            let space = (self.message_size - self.buf.len() % self.message_size) / 2;
+            let n = space.min(samples.len());
+            self.push_samples(&samples[..n]);
+            self.timestamp += (n / 2) as u64;
+            samples = &samples[n..];
+
+            if self.buf.len() >= self.buffer_bytes {
+                self.submit()?;
+            }
+        }
+
+        if (metadata.flags & BLADERF_META_FLAG_TX_BURST_END) != 0 {
+            self.in_burst = false;
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Zero-pad the current message and submit all buffered samples.
+    pub fn flush(&mut self) -> Result<()> {
+        self.pad_message()?;
+        if !self.buf.is_empty() {
+            self.submit()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TxStreamer<'_> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+        while self.queue.pending() > 0 {
+            let _ = block_on(self.queue.next_complete());
+        }
+        let _ = self.dev.enable_module(BLADERF_MODULE_TX, false);
+    }
+}
+
+impl BladeRf1 {
+    /// Enable the RX module and start streaming samples in the given format.
+    pub fn rx_streamer(
+        &self,
+        format: BladerfFormat,
+        config: StreamConfig,
+    ) -> Result<RxStreamer<'_>> {
+        let buffer_bytes = config.buffer_bytes()?;
+        let message_size = metadata_message_size(self.device_speed());
+
+        self.perform_format_config(BladerfDirection::BladerfRx, format)?;
+        if let Err(e) = self.enable_module(BLADERF_MODULE_RX, true) {
+            self.perform_format_deconfig(BladerfDirection::BladerfRx);
+            return Err(e);
+        }
+
+        let mut queue = self.interface.bulk_in_queue(ENDPOINT_RX_SAMPLES);
+        for _ in 0..config.num_transfers {
+            queue.submit(RequestBuffer::new(buffer_bytes));
+        }
+
+        Ok(RxStreamer {
+            dev: self,
+            format,
+            queue,
+            buffer_bytes,
+            message_size,
+            buf: Vec::new(),
+            pos: 0,
+            msg_timestamp: 0,
+            next_msg_timestamp: None,
+            check_counter: false,
+            next_counter: None,
+            stats: StreamStats::default(),
+        })
+    }
+
+    /// Enable the TX module and start streaming samples in the given format.
+    pub fn tx_streamer(
+        &self,
+        format: BladerfFormat,
+        config: StreamConfig,
+    ) -> Result<TxStreamer<'_>> {
+        let buffer_bytes = config.buffer_bytes()?;
+        let message_size = metadata_message_size(self.device_speed());
+
+        self.perform_format_config(BladerfDirection::BladerfTx, format)?;
+        if let Err(e) = self.enable_module(BLADERF_MODULE_TX, true) {
+            self.perform_format_deconfig(BladerfDirection::BladerfTx);
+            return Err(e);
+        }
+
+        Ok(TxStreamer {
+            dev: self,
+            format,
+            queue: self.interface.bulk_out_queue(ENDPOINT_TX_SAMPLES),
+            num_transfers: config.num_transfers,
+            buffer_bytes,
+            message_size,
+            buf: Vec::with_capacity(buffer_bytes),
+            in_burst: false,
+            timestamp: 0,
+            buf_transfer: None,
+            submitted: VecDeque::new(),
+            events: VecDeque::new(),
+        })
+    }
+
+    /// Stream RX samples in counter mode for `duration` and check the counter for gaps.
+    ///
+    /// The achievable throughput is bounded by the configured RX sample rate, so set it to the
+    /// rate that should be qualified beforehand. Counter mode is disabled again afterwards.
+    pub fn validate_throughput(
+        &self,
+        duration: Duration,
+        config: StreamConfig,
+    ) -> Result<ThroughputReport> {
+        self.set_counter_mode(true)?;
+
+        let result: Result<ThroughputReport> = (|| {
+            let mut streamer = self.rx_streamer(BladerfFormat::BladerfFormatSc16Q11, config)?;
+            streamer.set_counter_check(true);
+
+            let mut samples = vec![0i16; config.buffer_size * 2];
+            let start = Instant::now();
+            while start.elapsed() < duration {
+                streamer.read(&mut samples, None)?;
+            }
+            let elapsed = start.elapsed();
+
+            let stats = streamer.stats();
+            let samples_per_second = stats.samples_received as f64 / elapsed.as_secs_f64();
+            Ok(ThroughputReport {
+                elapsed,
+                samples_per_second,
+                bytes_per_second: samples_per_second * SAMPLE_SIZE as f64,
+                stats,
+            })
+        })();
+
+        /* Report a streaming error over a failure to leave counter mode */
+        let restore = self.set_counter_mode(false);
+        let report = result?;
+        restore?;
+        Ok(report)
+    }
+}
@@ -0,0 +1,125 @@
+use crate::bladerf::{
+    BladerfTrigger, BladerfTriggerRole, BladerfTriggerSignal, BladerfTriggerState,
+    BLADERF_MODULE_RX, BLADERF_MODULE_TX,
+};
+use crate::board::bladerf1::capabilities::{have_cap, BLADERF_CAP_TRX_SYNC_TRIG};
+use crate::board::bladerf1::BladeRf1;
+use crate::nios::access::NiosFormat;
+use crate::nios::constants::{NIOS_PKT_8X8_RX_TRIGGER_CTL, NIOS_PKT_8X8_TX_TRIGGER_CTL};
+use anyhow::{anyhow, Result};
+
+/* Trigger control register bits */
+const BLADERF_TRIGGER_REG_ARM: u8 = 1 << 0;
+const BLADERF_TRIGGER_REG_FIRE: u8 = 1 << 1;
+const BLADERF_TRIGGER_REG_MASTER: u8 = 1 << 2;
+const BLADERF_TRIGGER_REG_LINE: u8 = 1 << 3;
+
+impl BladeRf1 {
+    fn trigger_target(&self, channel: u8, _signal: BladerfTriggerSignal) -> Result<u8> {
+        if !have_cap(self.capabilities()?, BLADERF_CAP_TRX_SYNC_TRIG) {
+            return Err(anyhow!(
+                "Triggers are not supported by FPGA {}",
+                self.fpga_version()?
+            ));
+        }
+
+        /* All supported signals are routed through the same register */
+        match channel {
+            BLADERF_MODULE_RX => Ok(NIOS_PKT_8X8_RX_TRIGGER_CTL),
+            BLADERF_MODULE_TX => Ok(NIOS_PKT_8X8_TX_TRIGGER_CTL),
+            _ => Err(anyhow!("Invalid channel: {}", channel)),
+        }
+    }
+
+    fn trigger_read(&self, channel: u8, signal: BladerfTriggerSignal) -> Result<u8> {
+        let target = self.trigger_target(channel, signal)?;
+        <(u8, u8)>::read(&self.nios, target, 0)
+    }
+
+    fn trigger_write(&self, channel: u8, signal: BladerfTriggerSignal, regval: u8) -> Result<()> {
+        let target = self.trigger_target(channel, signal)?;
+        <(u8, u8)>::write(&self.nios, target, 0, regval)
+    }
+
+    /// Initialize a trigger configuration for a channel, taking the given role in the trigger
+    /// chain. The device is not reconfigured until the trigger is armed.
+    pub fn trigger_init(
+        &self,
+        channel: u8,
+        signal: BladerfTriggerSignal,
+        role: BladerfTriggerRole,
+    ) -> Result<BladerfTrigger> {
+        /* Fail early if the FPGA or channel does not support triggers */
+        self.trigger_target(channel, signal)?;
+
+        Ok(BladerfTrigger {
+            channel,
+            role,
+            signal,
+            options: 0,
+        })
+    }
+
+    /// Read back the role the device currently has for a trigger. A trigger that is not armed
+    /// is reported as disabled.
+    pub fn trigger_role(
+        &self,
+        channel: u8,
+        signal: BladerfTriggerSignal,
+    ) -> Result<BladerfTriggerRole> {
+        let regval = self.trigger_read(channel, signal)?;
+
+        Ok(if (regval & BLADERF_TRIGGER_REG_ARM) == 0 {
+            BladerfTriggerRole::BladerfTriggerRoleDisabled
+        } else if (regval & BLADERF_TRIGGER_REG_MASTER) != 0 {
+            BladerfTriggerRole::BladerfTriggerRoleMaster
+        } else {
+            BladerfTriggerRole::BladerfTriggerRoleSlave
+        })
+    }
+
+    /// Arm or disarm a trigger. While armed, streaming on the channel is held until the trigger
+    /// fires. A trigger with the disabled role clears the trigger configuration.
+    pub fn trigger_arm(&self, trigger: &BladerfTrigger, arm: bool) -> Result<()> {
+        let mut regval = self.trigger_read(trigger.channel, trigger.signal)?;
+
+        /* Reset any previous fire request */
+        regval &= !BLADERF_TRIGGER_REG_FIRE;
+
+        if arm {
+            regval |= BLADERF_TRIGGER_REG_ARM;
+        } else {
+            regval &= !BLADERF_TRIGGER_REG_ARM;
+        }
+
+        match trigger.role {
+            BladerfTriggerRole::BladerfTriggerRoleMaster => regval |= BLADERF_TRIGGER_REG_MASTER,
+            BladerfTriggerRole::BladerfTriggerRoleSlave => regval &= !BLADERF_TRIGGER_REG_MASTER,
+            BladerfTriggerRole::BladerfTriggerRoleDisabled => regval = 0,
+        }
+
+        self.trigger_write(trigger.channel, trigger.signal, regval)
+    }
+
+    /// Fire a trigger. Only the master of a trigger chain may fire.
+    pub fn trigger_fire(&self, trigger: &BladerfTrigger) -> Result<()> {
+        if trigger.role != BladerfTriggerRole::BladerfTriggerRoleMaster {
+            return Err(anyhow!("Only the trigger master can fire a trigger"));
+        }
+
+        let mut regval = self.trigger_read(trigger.channel, trigger.signal)?;
+        regval |= BLADERF_TRIGGER_REG_FIRE;
+        self.trigger_write(trigger.channel, trigger.signal, regval)
+    }
+
+    /// Read the current state of a trigger.
+    pub fn trigger_state(&self, trigger: &BladerfTrigger) -> Result<BladerfTriggerState> {
+        let regval = self.trigger_read(trigger.channel, trigger.signal)?;
+
+        Ok(BladerfTriggerState {
+            is_armed: (regval & BLADERF_TRIGGER_REG_ARM) != 0,
+            has_fired: (regval & BLADERF_TRIGGER_REG_LINE) != 0,
+            fire_requested: (regval & BLADERF_TRIGGER_REG_FIRE) != 0,
+        })
+    }
+}
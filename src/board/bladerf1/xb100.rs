@@ -0,0 +1,119 @@
+use crate::board::bladerf1::BladeRf1;
+use anyhow::Result;
+
+/**
+ * XB-100 GPIO pins, LEDs, and switches
+ *
+ * The discriminant of each variant is its bit in the expansion GPIO registers.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfXb100Pin {
+    BladerfXb100PinJ2_3 = 0x00000001,
+    BladerfXb100PinJ2_4 = 0x00000002,
+    BladerfXb100PinJ3_3 = 0x00000004,
+    BladerfXb100PinJ3_4 = 0x00000008,
+    BladerfXb100PinJ4_3 = 0x00000010,
+    BladerfXb100PinJ4_4 = 0x00000020,
+    BladerfXb100PinJ5_3 = 0x00000040,
+    BladerfXb100PinJ5_4 = 0x00000080,
+    BladerfXb100PinJ11_2 = 0x00000100,
+    BladerfXb100PinJ11_3 = 0x00000200,
+    BladerfXb100PinJ11_4 = 0x00000400,
+    BladerfXb100PinJ11_5 = 0x00000800,
+    BladerfXb100PinJ12_2 = 0x00001000,
+    BladerfXb100PinJ12_3 = 0x00002000,
+    BladerfXb100PinJ12_4 = 0x00004000,
+    BladerfXb100PinJ12_5 = 0x00008000,
+    BladerfXb100LedD1 = 0x00010000,
+    BladerfXb100LedD2 = 0x00020000,
+    BladerfXb100LedD3 = 0x00040000,
+    BladerfXb100LedD4 = 0x00080000,
+    BladerfXb100LedD5 = 0x00100000,
+    BladerfXb100LedD6 = 0x00200000,
+    BladerfXb100LedD7 = 0x00400000,
+    BladerfXb100LedD8 = 0x00800000,
+    BladerfXb100TledRed = 0x01000000,
+    BladerfXb100TledGreen = 0x02000000,
+    BladerfXb100TledBlue = 0x04000000,
+    /* On boards with push buttons, J6, J7 and J8 share the bits of DIP switches 1-3 */
+    BladerfXb100DipSw1 = 0x08000000,
+    BladerfXb100DipSw2 = 0x10000000,
+    BladerfXb100DipSw3 = 0x20000000,
+    BladerfXb100DipSw4 = 0x40000000,
+}
+
+impl BladerfXb100Pin {
+    /// Bit mask of the pin in the expansion GPIO registers.
+    pub fn mask(self) -> u32 {
+        self as u32
+    }
+}
+
+/* LEDs are driven by the FPGA; everything else defaults to an input. The LEDs
+ * are active-low, so driving these bits high turns them off. */
+const XB100_DEFAULT_OUTPUTS: u32 = BladerfXb100Pin::BladerfXb100LedD1 as u32
+    | BladerfXb100Pin::BladerfXb100LedD2 as u32
+    | BladerfXb100Pin::BladerfXb100LedD3 as u32
+    | BladerfXb100Pin::BladerfXb100LedD4 as u32
+    | BladerfXb100Pin::BladerfXb100LedD5 as u32
+    | BladerfXb100Pin::BladerfXb100LedD6 as u32
+    | BladerfXb100Pin::BladerfXb100LedD7 as u32
+    | BladerfXb100Pin::BladerfXb100LedD8 as u32
+    | BladerfXb100Pin::BladerfXb100TledRed as u32
+    | BladerfXb100Pin::BladerfXb100TledGreen as u32
+    | BladerfXb100Pin::BladerfXb100TledBlue as u32;
+
+impl BladeRf1 {
+    pub(crate) fn xb100_init(&self) -> Result<()> {
+        self.expansion_gpio_dir_write(XB100_DEFAULT_OUTPUTS)?;
+        self.expansion_gpio_write(XB100_DEFAULT_OUTPUTS)
+    }
+
+    pub fn xb100_gpio_read(&self) -> Result<u32> {
+        self.expansion_gpio_read()
+    }
+
+    /// Read the pins selected by `mask`. Bits outside of the mask are zero.
+    pub fn xb100_gpio_masked_read(&self, mask: u32) -> Result<u32> {
+        Ok(self.expansion_gpio_read()? & mask)
+    }
+
+    pub fn xb100_gpio_write(&self, val: u32) -> Result<()> {
+        self.expansion_gpio_write(val)
+    }
+
+    /// Write only the pins selected by `mask`.
+    pub fn xb100_gpio_masked_write(&self, mask: u32, val: u32) -> Result<()> {
+        self.expansion_gpio_masked_write(mask, val)
+    }
+
+    /// Read the GPIO direction register. A set bit denotes an output.
+    pub fn xb100_gpio_dir_read(&self) -> Result<u32> {
+        self.expansion_gpio_dir_read()
+    }
+
+    pub fn xb100_gpio_dir_write(&self, outputs: u32) -> Result<()> {
+        self.expansion_gpio_dir_write(outputs)
+    }
+
+    pub fn xb100_gpio_dir_masked_write(&self, mask: u32, outputs: u32) -> Result<()> {
+        self.expansion_gpio_dir_masked_write(mask, outputs)
+    }
+
+    pub fn xb100_pin_get(&self, pin: BladerfXb100Pin) -> Result<bool> {
+        Ok(self.xb100_gpio_masked_read(pin.mask())? != 0)
+    }
+
+    pub fn xb100_pin_set(&self, pin: BladerfXb100Pin, value: bool) -> Result<()> {
+        self.xb100_gpio_masked_write(pin.mask(), if value { pin.mask() } else { 0 })
+    }
+
+    /// Configure a pin as an output (`true`) or input (`false`).
+    pub fn xb100_pin_set_direction(&self, pin: BladerfXb100Pin, output: bool) -> Result<()> {
+        self.xb100_gpio_dir_masked_write(pin.mask(), if output { pin.mask() } else { 0 })
+    }
+
+    pub fn xb100_pin_get_direction(&self, pin: BladerfXb100Pin) -> Result<bool> {
+        Ok((self.xb100_gpio_dir_read()? & pin.mask()) != 0)
+    }
+}
@@ -0,0 +1,341 @@
+use crate::bladerf::{BLADERF_MODULE_RX, BLADERF_MODULE_TX};
+use crate::board::bladerf1::BladeRf1;
+use crate::hardware::adf4351::ADF4351;
+use anyhow::{anyhow, Result};
+
+/* XB-200 expansion GPIO bits */
+const BLADERF_XB_RF_ON: u32 = 0x0800;
+const BLADERF_XB_TX_ENABLE: u32 = 0x1000;
+const BLADERF_XB_RX_ENABLE: u32 = 0x2000;
+
+const BLADERF_XB_CONFIG_TX_PATH_MIX: u32 = 0x04;
+const BLADERF_XB_CONFIG_TX_PATH_BYPASS: u32 = 0x08;
+const BLADERF_XB_CONFIG_TX_BYPASS: u32 = 0x04;
+const BLADERF_XB_CONFIG_TX_BYPASS_MASK: u32 = 0x0C;
+const BLADERF_XB_CONFIG_RX_PATH_MIX: u32 = 0x10;
+const BLADERF_XB_CONFIG_RX_PATH_BYPASS: u32 = 0x20;
+const BLADERF_XB_CONFIG_RX_BYPASS: u32 = 0x10;
+const BLADERF_XB_CONFIG_RX_BYPASS_MASK: u32 = 0x30;
+
+const BLADERF_XB_TX_MASK: u32 = 0x0C000000;
+const BLADERF_XB_TX_SHIFT: u32 = 26;
+const BLADERF_XB_RX_MASK: u32 = 0x30000000;
+const BLADERF_XB_RX_SHIFT: u32 = 28;
+
+/* ADF4351 MUXOUT is routed to expansion GPIO bit 0 */
+const BLADERF_XB_ADF4351_MUXOUT: u32 = 0x1;
+
+/* LMS register 0x5A bits that swap I/Q to undo the spectral inversion of the mixer */
+const LMS_RX_SWAP: u8 = 0x40;
+const LMS_TX_SWAP: u8 = 0x20;
+
+/* ADF4351 register set for an Int-N 1248 MHz +3 dBm LO. Refer to the ADF4351
+ * reference manual for the register layout. */
+const XB200_ADF4351_REGS: [u32; 6] = [
+    0x00410000, 0x08008011, 0x60008E42, 0xC004B3, 0x99A16C, 0x580005,
+];
+
+/// XB-200 LO frequency. In the mixer path, a frequency f is translated to this minus f.
+pub const BLADERF_XB200_LO_FREQUENCY: u64 = 1248000000;
+
+/* The filter banks are only in use below this frequency */
+const XB200_AUTO_FILTER_MAX: u64 = 300000000;
+
+/* (low, high, filter) frequency ranges of the on-board filters, at their 1dB and 3dB points */
+const XB200_AUTO_1DB_BANDS: [(u64, u64, BladerfXb200Filter); 3] = [
+    (37774405, 59535436, BladerfXb200Filter::BladerfXb200_50M),
+    (128326173, 166711171, BladerfXb200Filter::BladerfXb200_144M),
+    (187593160, 245346403, BladerfXb200Filter::BladerfXb200_222M),
+];
+const XB200_AUTO_3DB_BANDS: [(u64, u64, BladerfXb200Filter); 3] = [
+    (34782924, 61899260, BladerfXb200Filter::BladerfXb200_50M),
+    (121956957, 178285631, BladerfXb200Filter::BladerfXb200_144M),
+    (177316330, 260697594, BladerfXb200Filter::BladerfXb200_222M),
+];
+
+/**
+ * XB-200 filter selection options
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfXb200Filter {
+    /** 50-54 MHz (6 meter band) filterbank */
+    BladerfXb200_50M = 0,
+    /** 144-148 MHz (2 meter band) filterbank */
+    BladerfXb200_144M,
+    /** 222-225 MHz (1.25 meter band) filterbank.
+     *
+     * Note that this filter option is technically wider, covering 206-235 MHz.
+     */
+    BladerfXb200_222M,
+    /** This option enables the RX/TX channel's custom filter bank path across
+     * the associated FILT and FILT-ANT SMA connectors on the XB-200 board.
+     *
+     * For reception, it is often possible to simply connect the RXFILT and
+     * RXFILT-ANT connectors with an SMA cable (effectively, "no filter"). This
+     * allows for reception of signals outside of the frequency range of the
+     * on-board filters, with some potential trade-off in signal quality.
+     *
+     * For transmission, <b>always</b> use an appropriate filter on the custom
+     * filter path to avoid spurious emissions.
+     */
+    BladerfXb200Custom,
+    /** When this option is selected, the other filter options are
+     * automatically selected depending on the RX or TX channel's current
+     * frequency, based upon the 1dB points of the on-board filters. For
+     * frequencies outside the range of the on-board filters, the custom path is
+     * selected.
+     */
+    BladerfXb200Auto1db,
+    /** When this option is selected, the other filter options are
+     * automatically selected depending on the RX or TX channel's current
+     * frequency, based upon the 3dB points of the on-board filters. For
+     * frequencies outside the range of the on-board filters, the custom path is
+     * selected.
+     */
+    BladerfXb200Auto3db,
+}
+
+/**
+ * XB-200 signal paths
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfXb200Path {
+    /**< Bypass the XB-200 mixer */
+    BladerfXb200Bypass = 0,
+    /**< Pass signals through the XB-200 mixer */
+    BladerfXb200Mix,
+}
+
+/* State of an attached XB-200 */
+pub(crate) struct XB200 {
+    adf4351: ADF4351,
+    /* Soft auto filter mode in use per channel, if any */
+    auto_filter: [Option<BladerfXb200Filter>; 2],
+}
+
+impl XB200 {
+    pub(crate) fn new(adf4351: ADF4351) -> Self {
+        Self {
+            adf4351,
+            auto_filter: [None, None],
+        }
+    }
+}
+
+fn xb200_channel_index(channel: u8) -> Result<usize> {
+    match channel {
+        BLADERF_MODULE_RX => Ok(0),
+        BLADERF_MODULE_TX => Ok(1),
+        _ => Err(anyhow!("Invalid channel: {}", channel)),
+    }
+}
+
+impl BladeRf1 {
+    pub(crate) fn xb200_attach(&self) -> Result<()> {
+        /* The XB-200 takes its reference from the SMB clock output */
+        let val = self.si5338.read(39)?;
+        self.si5338.write(39, val | 2)?;
+        self.si5338.write(34, 0x22)?;
+
+        let val = self.config_gpio_read()?;
+        self.config_gpio_write(val | 0x80000000)?;
+
+        self.expansion_gpio_dir_write(0x3C00383E)?;
+        self.expansion_gpio_write(0x800)?;
+
+        /* Load the ADF4351 registers. The LO is set to an Int-N 1248 MHz +3 dBm tone. */
+        let mut state = self.xb200.lock().unwrap();
+        let xb200 = state.get_or_insert_with(|| XB200::new(ADF4351::new(self.nios.clone())));
+        let loaded = xb200.adf4351.write_registers(&XB200_ADF4351_REGS);
+        if loaded.is_err() {
+            *state = None;
+        }
+        drop(state);
+        loaded?;
+
+        let val = self.expansion_gpio_read()?;
+        log::debug!("MUXOUT: {}", (val & BLADERF_XB_ADF4351_MUXOUT) != 0);
+
+        self.expansion_gpio_write(0x3C000800)
+    }
+
+    pub(crate) fn xb200_enable(&self, enable: bool) -> Result<()> {
+        let orig = self.expansion_gpio_read()?;
+        let val = if enable {
+            orig | BLADERF_XB_RF_ON
+        } else {
+            orig & !BLADERF_XB_RF_ON
+        };
+
+        if val != orig {
+            self.expansion_gpio_write(val)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn xb200_init(&self) -> Result<()> {
+        for channel in [BLADERF_MODULE_RX, BLADERF_MODULE_TX] {
+            self.xb200_set_path(channel, BladerfXb200Path::BladerfXb200Bypass)?;
+            self.xb200_set_filterbank(channel, BladerfXb200Filter::BladerfXb200Auto1db)?;
+        }
+        Ok(())
+    }
+
+    fn xb200_set_filterbank_mux(&self, channel: u8, filter: BladerfXb200Filter) -> Result<()> {
+        let (mask, shift) = if channel == BLADERF_MODULE_RX {
+            (BLADERF_XB_RX_MASK, BLADERF_XB_RX_SHIFT)
+        } else {
+            (BLADERF_XB_TX_MASK, BLADERF_XB_TX_SHIFT)
+        };
+
+        let orig = self.expansion_gpio_read()?;
+        let val = (orig & !mask) | ((filter as u32) << shift);
+
+        if orig != val {
+            self.expansion_gpio_write(val)?;
+        }
+        Ok(())
+    }
+
+    /// Select an XB-200 filter bank. The automatic modes re-select the filter on every
+    /// [`BladeRf1::set_frequency`].
+    pub fn xb200_set_filterbank(&self, channel: u8, filter: BladerfXb200Filter) -> Result<()> {
+        let index = xb200_channel_index(channel)?;
+
+        match filter {
+            BladerfXb200Filter::BladerfXb200Auto1db | BladerfXb200Filter::BladerfXb200Auto3db => {
+                /* Save which soft auto filter mode we're in */
+                self.xb200_state(|xb200| xb200.auto_filter[index] = Some(filter))?;
+
+                let frequency = self.get_frequency(channel)?;
+                self.xb200_auto_filter_selection(channel, frequency)
+            }
+            _ => {
+                /* Invalidate the soft auto filter mode entry */
+                self.xb200_state(|xb200| xb200.auto_filter[index] = None)?;
+                self.xb200_set_filterbank_mux(channel, filter)
+            }
+        }
+    }
+
+    pub fn xb200_get_filterbank(&self, channel: u8) -> Result<BladerfXb200Filter> {
+        let index = xb200_channel_index(channel)?;
+
+        if let Some(filter) = self.xb200_state(|xb200| xb200.auto_filter[index])? {
+            return Ok(filter);
+        }
+
+        let shift = if channel == BLADERF_MODULE_RX {
+            BLADERF_XB_RX_SHIFT
+        } else {
+            BLADERF_XB_TX_SHIFT
+        };
+
+        match (self.expansion_gpio_read()? >> shift) & 3 {
+            0 => Ok(BladerfXb200Filter::BladerfXb200_50M),
+            1 => Ok(BladerfXb200Filter::BladerfXb200_144M),
+            2 => Ok(BladerfXb200Filter::BladerfXb200_222M),
+            _ => Ok(BladerfXb200Filter::BladerfXb200Custom),
+        }
+    }
+
+    /* Select the filter matching a frequency when an automatic filter mode is in use */
+    pub(crate) fn xb200_auto_filter_selection(&self, channel: u8, frequency: u64) -> Result<()> {
+        if frequency >= XB200_AUTO_FILTER_MAX {
+            return Ok(());
+        }
+
+        let index = xb200_channel_index(channel)?;
+        /* Ranges are checked in order, as the 3dB points of adjacent filters overlap */
+        let bands = match self.xb200_state(|xb200| xb200.auto_filter[index])? {
+            Some(BladerfXb200Filter::BladerfXb200Auto1db) => XB200_AUTO_1DB_BANDS,
+            Some(BladerfXb200Filter::BladerfXb200Auto3db) => XB200_AUTO_3DB_BANDS,
+            _ => return Ok(()),
+        };
+
+        let filter = bands
+            .iter()
+            .find(|(low, high, _)| (*low..=*high).contains(&frequency))
+            .map(|(_, _, filter)| *filter)
+            .unwrap_or(BladerfXb200Filter::BladerfXb200Custom);
+
+        self.xb200_set_filterbank_mux(channel, filter)
+    }
+
+    /// Switch a channel between the XB-200 mixer and bypass paths.
+    pub fn xb200_set_path(&self, channel: u8, path: BladerfXb200Path) -> Result<()> {
+        xb200_channel_index(channel)?;
+
+        let swap = if channel == BLADERF_MODULE_RX {
+            LMS_RX_SWAP
+        } else {
+            LMS_TX_SWAP
+        };
+
+        let mut lval = self.lms.read(0x5A)?;
+        if path == BladerfXb200Path::BladerfXb200Mix {
+            lval |= swap;
+        } else {
+            lval &= !swap;
+        }
+        self.lms.write(0x5A, lval)?;
+
+        let mut val = self.expansion_gpio_read()?;
+        if (val & BLADERF_XB_RF_ON) == 0 {
+            self.xb200_attach()?;
+            val = self.expansion_gpio_read()?;
+        }
+
+        if channel == BLADERF_MODULE_RX {
+            val &= !BLADERF_XB_CONFIG_RX_BYPASS_MASK;
+            match path {
+                BladerfXb200Path::BladerfXb200Mix => {
+                    val |= BLADERF_XB_RX_ENABLE | BLADERF_XB_CONFIG_RX_PATH_MIX;
+                }
+                BladerfXb200Path::BladerfXb200Bypass => {
+                    val |= BLADERF_XB_CONFIG_RX_PATH_BYPASS;
+                    val &= !BLADERF_XB_RX_ENABLE;
+                }
+            }
+        } else {
+            val &= !BLADERF_XB_CONFIG_TX_BYPASS_MASK;
+            match path {
+                BladerfXb200Path::BladerfXb200Mix => {
+                    val |= BLADERF_XB_TX_ENABLE | BLADERF_XB_CONFIG_TX_PATH_MIX;
+                }
+                BladerfXb200Path::BladerfXb200Bypass => {
+                    val |= BLADERF_XB_CONFIG_TX_PATH_BYPASS;
+                    val &= !BLADERF_XB_TX_ENABLE;
+                }
+            }
+        }
+
+        self.expansion_gpio_write(val)
+    }
+
+    pub fn xb200_get_path(&self, channel: u8) -> Result<BladerfXb200Path> {
+        xb200_channel_index(channel)?;
+
+        let val = self.expansion_gpio_read()?;
+        let bypass = if channel == BLADERF_MODULE_RX {
+            BLADERF_XB_CONFIG_RX_BYPASS
+        } else {
+            BLADERF_XB_CONFIG_TX_BYPASS
+        };
+
+        if (val & bypass) != 0 {
+            Ok(BladerfXb200Path::BladerfXb200Mix)
+        } else {
+            Ok(BladerfXb200Path::BladerfXb200Bypass)
+        }
+    }
+
+    fn xb200_state<T>(&self, f: impl FnOnce(&mut XB200) -> T) -> Result<T> {
+        self.xb200
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(f)
+            .ok_or_else(|| anyhow!("XB-200 is not attached"))
+    }
+}
@@ -0,0 +1,188 @@
+use crate::board::bladerf1::BladeRf1;
+use anyhow::Result;
+
+/* XB-300 expansion GPIO bits */
+const BLADERF_XB_AUX_EN: u32 = 0x000002;
+const BLADERF_XB_TX_LED: u32 = 0x000010;
+const BLADERF_XB_RX_LED: u32 = 0x000020;
+const BLADERF_XB_TRX_TXN: u32 = 0x000040;
+const BLADERF_XB_TRX_RXN: u32 = 0x000080;
+const BLADERF_XB_TRX_MASK: u32 = 0x0000c0;
+const BLADERF_XB_PA_EN: u32 = 0x000200;
+const BLADERF_XB_LNA_ENN: u32 = 0x000400;
+const BLADERF_XB_CS: u32 = 0x010000;
+const BLADERF_XB_CSEL: u32 = 0x040000;
+const BLADERF_XB_DOUT: u32 = 0x100000;
+const BLADERF_XB_SCLK: u32 = 0x400000;
+
+/**
+ * XB-300 TRX setting
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfXb300Trx {
+    /**< TRX antenna operates as TX */
+    BladerfXb300TrxTx = 0,
+    /**< TRX antenna operates as RX */
+    BladerfXb300TrxRx,
+    /**< RX/TX is not set */
+    BladerfXb300TrxUnset,
+}
+
+/**
+ * XB-300 Amplifier selection
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BladerfXb300Amplifier {
+    /**< TX Power amplifier */
+    BladerfXb300AmpPa = 0,
+    /**< RX LNA */
+    BladerfXb300AmpLna,
+    /**< Auxillary Power amplifier */
+    BladerfXb300AmpPaAux,
+}
+
+impl BladeRf1 {
+    pub(crate) fn xb300_attach(&self) -> Result<()> {
+        let outputs = BLADERF_XB_TX_LED
+            | BLADERF_XB_RX_LED
+            | BLADERF_XB_TRX_MASK
+            | BLADERF_XB_PA_EN
+            | BLADERF_XB_LNA_ENN
+            | BLADERF_XB_CSEL
+            | BLADERF_XB_SCLK
+            | BLADERF_XB_CS;
+        self.expansion_gpio_dir_write(outputs)?;
+
+        self.expansion_gpio_write(BLADERF_XB_CS | BLADERF_XB_LNA_ENN)
+    }
+
+    pub(crate) fn xb300_enable(&self, _enable: bool) -> Result<()> {
+        self.expansion_gpio_write(BLADERF_XB_CS | BLADERF_XB_CSEL | BLADERF_XB_LNA_ENN)?;
+
+        /* Perform an initial conversion, discarding the result */
+        self.xb300_get_output_power()?;
+        Ok(())
+    }
+
+    pub(crate) fn xb300_init(&self) -> Result<()> {
+        log::debug!("Setting TRX path to TX");
+        self.xb300_set_trx(BladerfXb300Trx::BladerfXb300TrxTx)
+    }
+
+    /* Called on TX enable/disable, so the PA is only powered while transmitting */
+    pub(crate) fn xb300_tx_enable(&self, enable: bool) -> Result<()> {
+        if enable {
+            self.xb300_set_trx(BladerfXb300Trx::BladerfXb300TrxTx)?;
+            self.xb300_set_amplifier_enable(BladerfXb300Amplifier::BladerfXb300AmpPa, true)
+        } else {
+            self.xb300_set_amplifier_enable(BladerfXb300Amplifier::BladerfXb300AmpPa, false)?;
+            self.xb300_set_trx(BladerfXb300Trx::BladerfXb300TrxRx)
+        }
+    }
+
+    /// Configure the TRX antenna port for transmit or receive.
+    pub fn xb300_set_trx(&self, trx: BladerfXb300Trx) -> Result<()> {
+        let mut val = self.expansion_gpio_read()?;
+        val &= !BLADERF_XB_TRX_MASK;
+
+        match trx {
+            BladerfXb300Trx::BladerfXb300TrxRx => val |= BLADERF_XB_TRX_RXN,
+            BladerfXb300Trx::BladerfXb300TrxTx => val |= BLADERF_XB_TRX_TXN,
+            BladerfXb300Trx::BladerfXb300TrxUnset => {}
+        }
+
+        self.expansion_gpio_write(val)
+    }
+
+    pub fn xb300_get_trx(&self) -> Result<BladerfXb300Trx> {
+        let val = self.expansion_gpio_read()? & BLADERF_XB_TRX_MASK;
+
+        if val == 0 {
+            Ok(BladerfXb300Trx::BladerfXb300TrxUnset)
+        } else if (val & BLADERF_XB_TRX_RXN) != 0 {
+            Ok(BladerfXb300Trx::BladerfXb300TrxRx)
+        } else {
+            Ok(BladerfXb300Trx::BladerfXb300TrxTx)
+        }
+    }
+
+    /// Enable or disable an XB-300 amplifier. The PA is also switched automatically by
+    /// [`BladeRf1::enable_module`] on the TX channel.
+    pub fn xb300_set_amplifier_enable(
+        &self,
+        amp: BladerfXb300Amplifier,
+        enable: bool,
+    ) -> Result<()> {
+        let mut val = self.expansion_gpio_read()?;
+
+        match amp {
+            BladerfXb300Amplifier::BladerfXb300AmpPa => {
+                if enable {
+                    val |= BLADERF_XB_TX_LED | BLADERF_XB_PA_EN;
+                } else {
+                    val &= !(BLADERF_XB_TX_LED | BLADERF_XB_PA_EN);
+                }
+            }
+            BladerfXb300Amplifier::BladerfXb300AmpLna => {
+                /* The LNA enable is active-low */
+                if enable {
+                    val |= BLADERF_XB_RX_LED;
+                    val &= !BLADERF_XB_LNA_ENN;
+                } else {
+                    val &= !BLADERF_XB_RX_LED;
+                    val |= BLADERF_XB_LNA_ENN;
+                }
+            }
+            BladerfXb300Amplifier::BladerfXb300AmpPaAux => {
+                if enable {
+                    val |= BLADERF_XB_AUX_EN;
+                } else {
+                    val &= !BLADERF_XB_AUX_EN;
+                }
+            }
+        }
+
+        self.expansion_gpio_write(val)
+    }
+
+    pub fn xb300_get_amplifier_enable(&self, amp: BladerfXb300Amplifier) -> Result<bool> {
+        let val = self.expansion_gpio_read()?;
+
+        Ok(match amp {
+            BladerfXb300Amplifier::BladerfXb300AmpPa => (val & BLADERF_XB_PA_EN) != 0,
+            BladerfXb300Amplifier::BladerfXb300AmpLna => (val & BLADERF_XB_LNA_ENN) == 0,
+            BladerfXb300Amplifier::BladerfXb300AmpPaAux => (val & BLADERF_XB_AUX_EN) != 0,
+        })
+    }
+
+    /// Read the power detector, returning the PA output power in dBm.
+    pub fn xb300_get_output_power(&self) -> Result<f32> {
+        let rval =
+            self.expansion_gpio_read()? & !(BLADERF_XB_CS | BLADERF_XB_SCLK | BLADERF_XB_CSEL);
+
+        self.expansion_gpio_write(rval | BLADERF_XB_SCLK)?;
+        self.expansion_gpio_write(rval | BLADERF_XB_SCLK | BLADERF_XB_CS)?;
+
+        /* Clock the 10-bit ADC result out, MSB first, on clocks 2 through 11 */
+        let mut ret: u32 = 0;
+        for i in 1..=14 {
+            self.expansion_gpio_write(rval)?;
+            self.expansion_gpio_write(rval | BLADERF_XB_SCLK)?;
+            let dout = self.expansion_gpio_read()? & BLADERF_XB_DOUT;
+
+            if (2..=11).contains(&i) {
+                ret |= ((dout != 0) as u32) << (11 - i);
+            }
+        }
+
+        let volt = (1.8f32 / 1024.0f32) * ret as f32;
+        let volt2 = volt * volt;
+        let volt3 = volt * volt2;
+        let volt4 = volt2 * volt2;
+
+        Ok(
+            -503.933f32 * volt4 + 1409.489f32 * volt3 - 1487.84f32 * volt2 + 722.9793f32 * volt
+                - 114.7529f32,
+        )
+    }
+}
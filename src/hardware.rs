@@ -1,3 +1,4 @@
+pub mod adf4351;
 pub mod dac161s055;
 pub mod lms6002d;
 pub mod si5338;
@@ -0,0 +1,55 @@
+use crate::nios::constants::{NIOS_PKT_8X32_TARGET_ADF4351, NIOS_PKT_FLAG_WRITE};
+use crate::nios::packet::NiosPacket8x32;
+use crate::nios::{Nios, NiosInterface};
+use anyhow::Result;
+
+const PERIPHERAL_ENDPOINT_OUT: u8 = 0x02;
+const PERIPHERAL_ENDPOINT_IN: u8 = 0x82;
+
+/* The three LSBs of every register word select the register being written */
+const ADF4351_REG_ADDR_MASK: u32 = 0x7;
+
+/**
+ * ADF4351 wideband synthesizer, as found on the XB-200 transverter board.
+ *
+ * The part is write-only: register words are shifted out over SPI by the FPGA.
+ */
+pub struct ADF4351 {
+    interface: NiosInterface,
+}
+
+impl ADF4351 {
+    pub fn new(interface: NiosInterface) -> Self {
+        Self { interface }
+    }
+
+    /// Write a single 32-bit register word. The register address is encoded in bits [2:0].
+    pub fn write(&self, value: u32) -> Result<()> {
+        let mut request = NiosPacket8x32::new();
+        request.set(
+            NIOS_PKT_8X32_TARGET_ADF4351,
+            NIOS_PKT_FLAG_WRITE,
+            0x0,
+            value,
+        );
+
+        let _response = self.interface.nios_send(
+            PERIPHERAL_ENDPOINT_IN,
+            PERIPHERAL_ENDPOINT_OUT,
+            request.into_vec(),
+        )?;
+        Ok(())
+    }
+
+    /// Program a full register set. Registers are written in order from 5 down to 0,
+    /// as writing R0 latches the new configuration.
+    pub fn write_registers(&self, regs: &[u32; 6]) -> Result<()> {
+        let mut ordered = *regs;
+        ordered.sort_by_key(|reg| std::cmp::Reverse(reg & ADF4351_REG_ADDR_MASK));
+
+        for reg in ordered {
+            self.write(reg)?;
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,19 @@
 use crate::nios::constants::{
     NIOS_PKT_8X16_TARGET_VCTCXO_DAC, NIOS_PKT_8X8_TARGET_LMS6, NIOS_PKT_FLAG_WRITE,
 };
-use crate::nios::packet8x16::NiosPacket8x16;
-use crate::nios::Nios;
+use crate::nios::packet::NiosPacket8x16;
+use crate::nios::{Nios, NiosInterface};
 use anyhow::Result;
-use nusb::Interface;
 
 const PERIPHERAL_ENDPOINT_OUT: u8 = 0x02;
 const PERIPHERAL_ENDPOINT_IN: u8 = 0x82;
 
 pub struct DAC161S055 {
-    interface: Interface,
+    interface: NiosInterface,
 }
 
 impl DAC161S055 {
-    pub fn new(interface: Interface) -> Self {
+    pub fn new(interface: NiosInterface) -> Self {
         Self { interface }
     }
 
@@ -34,9 +33,9 @@ impl DAC161S055 {
             request.into_vec(),
         )?;
 
-        //Ok(NiosPacket8x16::reuse(response).data())
+        //Ok(NiosPacket8x16::from_vec(response).data())
         /* Write DAC value to channel 0 */
-        request = NiosPacket8x16::reuse(response);
+        request = NiosPacket8x16::from_vec(response)?;
         request.set(
             NIOS_PKT_8X16_TARGET_VCTCXO_DAC,
             NIOS_PKT_FLAG_WRITE,
@@ -50,7 +49,7 @@ impl DAC161S055 {
             request.into_vec(),
         )?;
 
-        Ok(NiosPacket8x16::reuse(response).data())
+        Ok(NiosPacket8x16::from_vec(response)?.data())
 
         // /* Ensure device is in write-through mode */
         // status = dev->backend->vctcxo_dac_write(dev, 0x28, 0x0000);
@@ -1,10 +1,11 @@
-use crate::bladerf::{BladerfLoopback, BLADERF_MODULE_RX, BLADERF_MODULE_TX};
+use crate::bladerf::{BladerfLoopback, BladerfLpfMode, BLADERF_MODULE_RX, BLADERF_MODULE_TX};
 use crate::board::bladerf1::{BladerfLnaGain, BLADERF_FREQUENCY_MAX, BLADERF_FREQUENCY_MIN};
+use crate::nios::batch::NiosBatch;
 use crate::nios::constants::{NIOS_PKT_8X8_TARGET_LMS6, NIOS_PKT_FLAG_READ, NIOS_PKT_FLAG_WRITE};
-use crate::nios::packet8x8::NiosPacket8x8;
-use crate::nios::Nios;
+use crate::nios::packet::NiosPacket8x8;
+use crate::nios::{Nios, NiosInterface};
 use anyhow::{anyhow, Result};
-use nusb::Interface;
+use std::str::FromStr;
 // use std::cmp::PartialEq;
 
 const ENDPOINT_OUT: u8 = 0x02;
@@ -408,6 +409,7 @@ pub enum LmsBw {
 /**
  * LNA options
  */
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LmsLna {
     /**< Disable all LNAs */
     LnaNone,
@@ -432,6 +434,7 @@ pub enum LmsLbp {
 /**
  * PA Selection
  */
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LmsPa {
     /**< AUX PA Enable (for RF Loopback) */
     PaAux,
@@ -463,12 +466,108 @@ pub struct LmsXcvrConfig {
     rx_bw: LmsBw,
 }
 
+/* Registers in LMS_REG_DUMPSET that are not written back by restore(). These
+ * are either read-only (chip ID, DC calibration results and status), or DC
+ * calibration control registers where writing back the captured value could
+ * strobe a calibration or load.
+ */
+const LMS_REG_RESTORE_SKIP: [u8; 17] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, /* Top level DC calibration, chip ID */
+    0x30, 0x31, 0x32, 0x33, /* TX LPF DC calibration */
+    0x50, 0x51, 0x52, 0x53, /* RX LPF DC calibration */
+    0x60, 0x61, 0x62, 0x63, /* RX VGA2 DC calibration */
+];
+
+/**
+ * Captured values of the registers in LMS_REG_DUMPSET
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct LmsRegisterSnapshot {
+    regs: Vec<(u8, u8)>,
+}
+
+impl LmsRegisterSnapshot {
+    /// (address, value) pairs, in LMS_REG_DUMPSET order.
+    pub fn registers(&self) -> &[(u8, u8)] {
+        &self.regs
+    }
+
+    /// Captured value of a register, if it is part of the snapshot.
+    pub fn get(&self, addr: u8) -> Option<u8> {
+        self.regs
+            .iter()
+            .find(|(reg_addr, _)| *reg_addr == addr)
+            .map(|(_, value)| *value)
+    }
+}
+
+/* Serialized as a flat JSON object of hex strings:
+ *
+ *      {
+ *        "0x00": "0x1f",
+ *        ...
+ *      }
+ */
+impl std::fmt::Display for LmsRegisterSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{{")?;
+        for (i, (addr, value)) in self.regs.iter().enumerate() {
+            let separator = if i + 1 < self.regs.len() { "," } else { "" };
+            writeln!(f, "  \"0x{:02x}\": \"0x{:02x}\"{}", addr, value, separator)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl FromStr for LmsRegisterSnapshot {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let body = s
+            .trim()
+            .strip_prefix('{')
+            .and_then(|body| body.strip_suffix('}'))
+            .ok_or_else(|| anyhow!("LMS register snapshot must be enclosed in braces"))?;
+
+        let parse_hex = |field: &str| -> Result<u8> {
+            let field = field.trim().trim_matches('"');
+            let digits = field
+                .strip_prefix("0x")
+                .or_else(|| field.strip_prefix("0X"))
+                .unwrap_or(field);
+            u8::from_str_radix(digits, 16)
+                .map_err(|e| anyhow!("Invalid value \"{}\": {}", field, e))
+        };
+
+        let mut regs = Vec::with_capacity(LMS_REG_DUMPSET.len());
+        for entry in body
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (addr, value) = entry
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Expected \"addr\": \"value\", got \"{}\"", entry))?;
+            let addr = parse_hex(addr)?;
+            if !LMS_REG_DUMPSET.contains(&addr) {
+                return Err(anyhow!(
+                    "Register 0x{:02x} is not part of the LMS dump set",
+                    addr
+                ));
+            }
+            regs.push((addr, parse_hex(value)?));
+        }
+
+        Ok(Self { regs })
+    }
+}
+
 pub struct LMS6002D {
-    interface: Interface,
+    interface: NiosInterface,
 }
 
 impl LMS6002D {
-    pub fn new(interface: Interface) -> Self {
+    pub fn new(interface: NiosInterface) -> Self {
         Self { interface }
     }
     pub fn read(&self, addr: u8) -> Result<u8> {
@@ -478,7 +577,7 @@ impl LMS6002D {
         let response = self
             .interface
             .nios_send(ENDPOINT_IN, ENDPOINT_OUT, request.into_vec())?;
-        Ok(NiosPacket8x8::reuse(response).data())
+        Ok(NiosPacket8x8::from_vec(response)?.data())
     }
 
     pub fn write(&self, addr: u8, data: u8) -> Result<u8> {
@@ -488,7 +587,28 @@ impl LMS6002D {
         let response = self
             .interface
             .nios_send(ENDPOINT_IN, ENDPOINT_OUT, request.into_vec())?;
-        Ok(NiosPacket8x8::reuse(response).data())
+        Ok(NiosPacket8x8::from_vec(response)?.data())
+    }
+
+    /// Capture the registers in LMS_REG_DUMPSET.
+    pub fn dump(&self) -> Result<LmsRegisterSnapshot> {
+        let regs = LMS_REG_DUMPSET
+            .iter()
+            .map(|&addr| Ok((addr, self.read(addr)?)))
+            .collect::<Result<Vec<(u8, u8)>>>()?;
+        Ok(LmsRegisterSnapshot { regs })
+    }
+
+    /// Write back a snapshot captured by dump(). Read-only and DC calibration control
+    /// registers are skipped, so DC calibration should be re-run or re-applied afterwards.
+    pub fn restore(&self, snapshot: &LmsRegisterSnapshot) -> Result<()> {
+        for &(addr, value) in snapshot.registers() {
+            if LMS_REG_RESTORE_SKIP.contains(&addr) {
+                continue;
+            }
+            self.write(addr, value)?;
+        }
+        Ok(())
     }
 
     pub fn set(&self, addr: u8, mask: u8) -> Result<()> {
@@ -656,12 +776,15 @@ impl LMS6002D {
     }
 
     pub fn lms_get_loopback_mode(&self) -> Result<BladerfLoopback> {
-        let mut loopback = BladerfLoopback::BladerfLbNone;
-        let mut lben_lbrfen: u8 = 0;
-        let mut loopbben: u8 = 0;
+        let lben_lbrfen = self.read(0x08)?;
+        let loopbben = self.read(0x46)?;
 
-        lben_lbrfen = self.read(0x08)?;
-        loopbben = self.read(0x46)?;
+        Ok(Self::decode_loopback_mode(lben_lbrfen, loopbben))
+    }
+
+    /* Loopback mode from the values of registers 0x08 and 0x46 */
+    fn decode_loopback_mode(lben_lbrfen: u8, loopbben: u8) -> BladerfLoopback {
+        let mut loopback = BladerfLoopback::BladerfLbNone;
 
         match lben_lbrfen & 0x7 {
             LBRFEN_LNA1 => {
@@ -694,7 +817,7 @@ impl LMS6002D {
             _ => {}
         }
 
-        Ok(loopback)
+        loopback
     }
 
     pub fn is_loopback_enabled(&self) -> Result<bool> {
@@ -714,20 +837,25 @@ impl LMS6002D {
             0x25
         };
 
-        let mut regval = self.read(addr)?;
+        let regval = self.read(addr)?;
 
         let lb_enabled: bool = self.is_loopback_enabled()?;
 
+        self.write(
+            addr,
+            Self::pll_config_regval(regval, lb_enabled, freqsel, low_band),
+        )
+    }
+
+    fn pll_config_regval(regval: u8, lb_enabled: bool, freqsel: u8, low_band: bool) -> u8 {
         if lb_enabled == false {
             /* Loopback not enabled - update the PLL output buffer. */
             let selout = if low_band { 1 } else { 2 };
-            regval = (freqsel << 2) | selout;
+            (freqsel << 2) | selout
         } else {
             /* Loopback is enabled - don't touch PLL output buffer. */
-            regval = (regval & !0xfc) | (freqsel << 2);
+            (regval & !0xfc) | (freqsel << 2)
         }
-
-        self.write(addr, regval)
     }
 
     /* These values are the max counts we've seen (experimentally) between
@@ -1048,54 +1176,68 @@ impl LMS6002D {
 
         f.vcocap_result = 0xff;
 
-        /* Turn on the DSMs */
-        data = self.read(0x09)?;
-        data |= 0x05;
-        self.write(0x09, data)?; //.expect("Failed to turn on DSMs\n");
+        let pll_cfg_addr: u8 = if module == BLADERF_MODULE_TX {
+            0x15
+        } else {
+            0x25
+        };
 
-        /* Write the initial vcocap estimate first to allow for adequate time for
-         * VTUNE to stabilize. We need to be sure to keep the upper bits of
-         * this register and perform a RMW, as bit 7 is VOVCOREG[0]. */
-        let mut result = self.read(base + 9);
-        if result.is_err() {
-            self.turn_off_dsms()?;
-        }
-        vcocap_reg_state = result?;
+        /* Fetch every register that is updated with a read-modify-write, so the
+         * writes below can be sent as a single batch */
+        let mut batch = NiosBatch::new();
+        let dsm_idx = batch.read::<u8, u8>(NIOS_PKT_8X8_TARGET_LMS6, 0x09);
+        let vcocap_idx = batch.read::<u8, u8>(NIOS_PKT_8X8_TARGET_LMS6, base + 9);
+        let pll_cfg_idx = batch.read::<u8, u8>(NIOS_PKT_8X8_TARGET_LMS6, pll_cfg_addr);
+        let lben_lbrfen_idx = batch.read::<u8, u8>(NIOS_PKT_8X8_TARGET_LMS6, 0x08);
+        let loopbben_idx = batch.read::<u8, u8>(NIOS_PKT_8X8_TARGET_LMS6, 0x46);
+        let regs = batch.submit(&self.interface)?;
+
+        let loopback = Self::decode_loopback_mode(
+            regs.data::<u8, u8>(lben_lbrfen_idx)?,
+            regs.data::<u8, u8>(loopbben_idx)?,
+        );
+        let pll_cfg = Self::pll_config_regval(
+            regs.data::<u8, u8>(pll_cfg_idx)?,
+            loopback != BladerfLoopback::BladerfLbNone,
+            f.freqsel,
+            (f.flags & LMS_FREQ_FLAGS_LOW_BAND) != 0,
+        );
 
-        vcocap_reg_state &= !0x3f;
+        /* Keep the upper bits of the VCOCAP register, as bit 7 is VOVCOREG[0] */
+        vcocap_reg_state = regs.data::<u8, u8>(vcocap_idx)? & !0x3f;
+        assert!(f.vcocap <= VCOCAP_MAX_VALUE);
 
-        result = self.write_vcocap(base, f.vcocap, vcocap_reg_state);
-        if result.is_err() {
-            self.turn_off_dsms()?;
-        }
+        let mut batch = NiosBatch::new();
 
-        result = self.write_pll_config(module, f.freqsel, (f.flags & LMS_FREQ_FLAGS_LOW_BAND) != 0);
-        if result.is_err() {
-            self.turn_off_dsms()?;
-        }
+        /* Turn on the DSMs */
+        data = regs.data::<u8, u8>(dsm_idx)? | 0x05;
+        batch.write(NIOS_PKT_8X8_TARGET_LMS6, 0x09, data);
+
+        /* Write the initial vcocap estimate first to allow for adequate time for
+         * VTUNE to stabilize. */
+        batch.write(
+            NIOS_PKT_8X8_TARGET_LMS6,
+            base + 9,
+            f.vcocap | vcocap_reg_state,
+        );
+
+        batch.write(NIOS_PKT_8X8_TARGET_LMS6, pll_cfg_addr, pll_cfg);
 
         data = (f.nint >> 1) as u8;
-        result = self.write(pll_base + 0, data);
-        if result.is_err() {
-            self.turn_off_dsms()?;
-        }
+        batch.write(NIOS_PKT_8X8_TARGET_LMS6, pll_base, data);
 
         data = (((f.nint & 1) << 7) as u32 | ((f.nfrac >> 16) & 0x7f)) as u8;
-        result = self.write(pll_base + 1, data);
-        if result.is_err() {
-            self.turn_off_dsms()?;
-        }
+        batch.write(NIOS_PKT_8X8_TARGET_LMS6, pll_base + 1, data);
 
         data = ((f.nfrac >> 8) & 0xff) as u8;
-        result = self.write(pll_base + 2, data);
-        if result.is_err() {
-            self.turn_off_dsms()?;
-        }
+        batch.write(NIOS_PKT_8X8_TARGET_LMS6, pll_base + 2, data);
 
         data = (f.nfrac & 0xff) as u8;
-        result = self.write(pll_base + 3, data);
-        if result.is_err() {
+        batch.write(NIOS_PKT_8X8_TARGET_LMS6, pll_base + 3, data);
+
+        if let Err(e) = batch.submit(&self.interface) {
             self.turn_off_dsms()?;
+            return Err(e);
         }
 
         /* Perform tuning algorithm unless we've been instructed to just use
@@ -1133,4 +1275,504 @@ impl LMS6002D {
         self.set_precalculated_frequency(channel, &mut f)?;
         Ok(f)
     }
+
+    /* DC offset values are normalized to [-2048, 2048]. The RX registers only
+     * provide a 6-bit magnitude plus a sign bit (bit 6), while the TX registers
+     * are 8-bit offset binary where 0x00 = -16, 0x80 = 0 and 0xff = 15.9375 */
+    fn scale_dc_offset(module: u8, mut value: i16) -> Result<u8> {
+        match module {
+            BLADERF_MODULE_RX => {
+                /* RX only has 6 bits of scale to work with, remove normalization */
+                value >>= 5;
+
+                if value < 0 {
+                    /* Clamp */
+                    let magnitude = if value <= -64 {
+                        0x3f
+                    } else {
+                        (-value) as u8 & 0x3f
+                    };
+
+                    /* This register uses bit 6 to denote a negative value */
+                    Ok(magnitude | (1 << 6))
+                } else if value >= 64 {
+                    /* Clamp */
+                    Ok(0x3f)
+                } else {
+                    Ok(value as u8 & 0x3f)
+                }
+            }
+            BLADERF_MODULE_TX => {
+                /* TX only has 7 bits of scale to work with, remove normalization */
+                value >>= 4;
+
+                if value >= 0 {
+                    let value = if value >= 128 { 0x7f } else { value as u8 };
+                    Ok((value & 0x7f) | 0x80)
+                } else if value <= -128 {
+                    Ok(0x00)
+                } else {
+                    Ok(value as u8 & 0x7f)
+                }
+            }
+            _ => Err(anyhow!("Invalid module: {}", module)),
+        }
+    }
+
+    fn set_dc_offset_reg(&self, module: u8, addr: u8, value: i16) -> Result<u8> {
+        let tmp = if module == BLADERF_MODULE_RX {
+            /* Bit 7 is unrelated to lms dc correction, save its state */
+            self.read(addr)? & (1 << 7)
+        } else {
+            0
+        };
+
+        let regval = Self::scale_dc_offset(module, value)?;
+
+        self.write(addr, regval | tmp)
+    }
+
+    fn get_dc_offset_reg(&self, module: u8, addr: u8) -> Result<i16> {
+        let tmp = self.read(addr)?;
+
+        match module {
+            BLADERF_MODULE_RX => {
+                /* Mask out an unrelated control bit */
+                let tmp = tmp & 0x7f;
+
+                /* Determine sign */
+                let value = if (tmp & (1 << 6)) != 0 {
+                    -((tmp & 0x3f) as i16)
+                } else {
+                    (tmp & 0x3f) as i16
+                };
+
+                /* Renormalize to 2048 */
+                Ok(value << 5)
+            }
+            BLADERF_MODULE_TX => {
+                /* Remove the 0x80 offset and renormalize to 2048 */
+                Ok((tmp as i16 - 128) << 4)
+            }
+            _ => Err(anyhow!("Invalid module: {}", module)),
+        }
+    }
+
+    pub fn set_dc_offset_i(&self, module: u8, value: i16) -> Result<u8> {
+        let addr = if module == BLADERF_MODULE_TX {
+            0x42
+        } else {
+            0x71
+        };
+        self.set_dc_offset_reg(module, addr, value)
+    }
+
+    pub fn set_dc_offset_q(&self, module: u8, value: i16) -> Result<u8> {
+        let addr = if module == BLADERF_MODULE_TX {
+            0x43
+        } else {
+            0x72
+        };
+        self.set_dc_offset_reg(module, addr, value)
+    }
+
+    pub fn get_dc_offset_i(&self, module: u8) -> Result<i16> {
+        let addr = if module == BLADERF_MODULE_TX {
+            0x42
+        } else {
+            0x71
+        };
+        self.get_dc_offset_reg(module, addr)
+    }
+
+    pub fn get_dc_offset_q(&self, module: u8) -> Result<i16> {
+        let addr = if module == BLADERF_MODULE_TX {
+            0x43
+        } else {
+            0x72
+        };
+        self.get_dc_offset_reg(module, addr)
+    }
+
+    /// Select which PA is enabled. All other PAs are powered down.
+    pub fn select_pa(&self, pa: LmsPa) -> Result<u8> {
+        let mut data = self.read(0x44)?;
+
+        /* Disable PA1, PA2, and AUX PA - we'll enable as requested below. */
+        data &= !0x1c;
+
+        /* AUX PA powered down */
+        data |= 1 << 1;
+
+        match pa {
+            LmsPa::PaAux => data &= !(1 << 1),
+            LmsPa::Pa1 => data |= 2 << 2,
+            LmsPa::Pa2 => data |= 4 << 2,
+            LmsPa::PaNone => {}
+        }
+
+        self.write(0x44, data)
+    }
+
+    /// Select which LNA is enabled.
+    pub fn select_lna(&self, lna: LmsLna) -> Result<u8> {
+        let mut data = self.read(0x75)?;
+
+        data &= !(3 << 4);
+        data |= ((lna as u8) & 3) << 4;
+
+        self.write(0x75, data)
+    }
+
+    pub fn rxvga1_enable(&self, enable: bool) -> Result<u8> {
+        /* Enable bit is in reserved register documented in this thread:
+         *  https://groups.google.com/forum/#!topic/limemicro-opensource/8iTannzlfzg
+         */
+        let mut data = self.read(0x7d)?;
+        if enable {
+            data &= !(1 << 3);
+        } else {
+            data |= 1 << 3;
+        }
+        self.write(0x7d, data)
+    }
+
+    pub fn rxvga2_enable(&self, enable: bool) -> Result<u8> {
+        let mut data = self.read(0x64)?;
+        if enable {
+            data |= 1 << 1;
+        } else {
+            data &= !(1 << 1);
+        }
+        self.write(0x64, data)
+    }
+
+    pub fn lpf_get_mode(&self, module: u8) -> Result<BladerfLpfMode> {
+        let reg: u8 = if module == BLADERF_MODULE_RX {
+            0x54
+        } else {
+            0x34
+        };
+
+        let data_l = self.read(reg)?;
+        let data_h = self.read(reg + 1)?;
+
+        let lpf_enabled = (data_l & (1 << 1)) != 0;
+        let lpf_bypassed = (data_h & (1 << 6)) != 0;
+
+        match (lpf_enabled, lpf_bypassed) {
+            (true, false) => Ok(BladerfLpfMode::BladerfLpfNormal),
+            (false, true) => Ok(BladerfLpfMode::BladerfLpfBypassed),
+            (false, false) => Ok(BladerfLpfMode::BladerfLpfDisabled),
+            (true, true) => Err(anyhow!(
+                "Invalid LPF configuration: 0x{:02x}, 0x{:02x}",
+                data_l,
+                data_h
+            )),
+        }
+    }
+
+    pub fn lpf_set_mode(&self, module: u8, mode: BladerfLpfMode) -> Result<()> {
+        let reg: u8 = if module == BLADERF_MODULE_RX {
+            0x54
+        } else {
+            0x34
+        };
+
+        let mut data_l = self.read(reg)?;
+        let mut data_h = self.read(reg + 1)?;
+
+        match mode {
+            BladerfLpfMode::BladerfLpfNormal => {
+                data_l |= 1 << 1; /* Enable LPF */
+                data_h &= !(1 << 6); /* Disable LPF bypass */
+            }
+            BladerfLpfMode::BladerfLpfBypassed => {
+                data_l &= !(1 << 1); /* Power down LPF */
+                data_h |= 1 << 6; /* Enable LPF bypass */
+            }
+            BladerfLpfMode::BladerfLpfDisabled => {
+                data_l &= !(1 << 1); /* Power down LPF */
+                data_h &= !(1 << 6); /* Disable LPF bypass */
+            }
+        }
+
+        self.write(reg, data_l)?;
+        self.write(reg + 1, data_h)?;
+        Ok(())
+    }
+
+    /// Read back the current PLL configuration of a module.
+    pub fn get_frequency(&self, module: u8) -> Result<LmsFreq> {
+        let base: u8 = if module == BLADERF_MODULE_RX {
+            0x20
+        } else {
+            0x10
+        };
+
+        let mut f = LmsFreq::default();
+
+        let data = self.read(base)?;
+        f.nint = (data as u16) << 1;
+
+        let data = self.read(base + 1)?;
+        f.nint |= ((data & 0x80) >> 7) as u16;
+        f.nfrac = ((data & 0x7f) as u32) << 16;
+
+        let data = self.read(base + 2)?;
+        f.nfrac |= (data as u32) << 8;
+
+        let data = self.read(base + 3)?;
+        f.nfrac |= data as u32;
+
+        let data = self.read(base + 5)?;
+        f.freqsel = data >> 2;
+        if (f.freqsel & 7) >= 3 {
+            f.x = 1 << ((f.freqsel & 7) - 3);
+        }
+
+        let data = self.read(base + 9)?;
+        f.vcocap = data & 0x3f;
+
+        Ok(f)
+    }
+
+    pub fn frequency_to_hz(f: &LmsFreq) -> u32 {
+        let pll_coeff = ((f.nint as u64) << 23) + f.nfrac as u64;
+        let div = (f.x as u64) << 23;
+
+        ((LMS_REFERENCE_HZ as u64 * pll_coeff) + (div >> 1))
+            .checked_div(div)
+            .unwrap_or(0) as u32
+    }
+
+    /* Configure the "switches" along the loopback path */
+    fn loopback_path(&self, mode: BladerfLoopback) -> Result<()> {
+        let mut loopbben = self.read(0x46)?;
+        let mut lben_lbrf = self.read(0x08)?;
+
+        /* Default to baseband loopback being disabled  */
+        loopbben &= !LOOBBBEN_MASK;
+
+        /* Default to RF and BB loopback options being disabled */
+        lben_lbrf &= !(LBRFEN_MASK | LBEN_MASK);
+
+        match mode {
+            BladerfLoopback::BladerfLbNone => {}
+            BladerfLoopback::BladerfLbBbTxlpfRxvga2 => {
+                loopbben |= LOOPBBEN_TXLPF;
+                lben_lbrf |= LBEN_VGA2IN;
+            }
+            BladerfLoopback::BladerfLbBbTxvga1Rxvga2 => {
+                loopbben |= LOOPBBEN_TXVGA;
+                lben_lbrf |= LBEN_VGA2IN;
+            }
+            BladerfLoopback::BladerfLbBbTxlpfRxlpf => {
+                loopbben |= LOOPBBEN_TXLPF;
+                lben_lbrf |= LBEN_LPFIN;
+            }
+            BladerfLoopback::BladerfLbBbTxvga1Rxlpf => {
+                loopbben |= LOOPBBEN_TXVGA;
+                lben_lbrf |= LBEN_LPFIN;
+            }
+            BladerfLoopback::BladerfLbRfLna1 => lben_lbrf |= LBRFEN_LNA1,
+            BladerfLoopback::BladerfLbRfLna2 => lben_lbrf |= LBRFEN_LNA2,
+            BladerfLoopback::BladerfLbRfLna3 => lben_lbrf |= LBRFEN_LNA3,
+            _ => return Err(anyhow!("Invalid loopback mode: {:?}", mode)),
+        }
+
+        self.write(0x46, loopbben)?;
+        self.write(0x08, lben_lbrf)?;
+        Ok(())
+    }
+
+    /* Configure the RX side of the loopback path */
+    fn loopback_rx(&self, mode: BladerfLoopback) -> Result<()> {
+        let lpf_mode = self.lpf_get_mode(BLADERF_MODULE_RX)?;
+
+        match mode {
+            BladerfLoopback::BladerfLbNone => {
+                /* Ensure all RX blocks are enabled */
+                self.rxvga1_enable(true)?;
+
+                if lpf_mode == BladerfLpfMode::BladerfLpfDisabled {
+                    self.lpf_set_mode(BLADERF_MODULE_RX, BladerfLpfMode::BladerfLpfNormal)?;
+                }
+
+                self.rxvga2_enable(true)?;
+
+                /* Restore RX LNA selection */
+                let f = self.get_frequency(BLADERF_MODULE_RX)?;
+                if Self::frequency_to_hz(&f) < BLADERF1_BAND_HIGH {
+                    self.select_lna(LmsLna::Lna1)?;
+                } else {
+                    self.select_lna(LmsLna::Lna2)?;
+                }
+            }
+            BladerfLoopback::BladerfLbBbTxvga1Rxvga2 | BladerfLoopback::BladerfLbBbTxlpfRxvga2 => {
+                /* Ensure RXVGA2 is enabled */
+                self.rxvga2_enable(true)?;
+
+                /* RXLPF must be disabled */
+                self.lpf_set_mode(BLADERF_MODULE_RX, BladerfLpfMode::BladerfLpfDisabled)?;
+            }
+            BladerfLoopback::BladerfLbBbTxlpfRxlpf | BladerfLoopback::BladerfLbBbTxvga1Rxlpf => {
+                /* RXVGA1 must be disabled */
+                self.rxvga1_enable(false)?;
+
+                /* Enable the RXLPF if needed */
+                if lpf_mode == BladerfLpfMode::BladerfLpfDisabled {
+                    self.lpf_set_mode(BLADERF_MODULE_RX, BladerfLpfMode::BladerfLpfNormal)?;
+                }
+
+                /* Ensure RXVGA2 is enabled */
+                self.rxvga2_enable(true)?;
+            }
+            BladerfLoopback::BladerfLbRfLna1
+            | BladerfLoopback::BladerfLbRfLna2
+            | BladerfLoopback::BladerfLbRfLna3 => {
+                let lna = match mode {
+                    BladerfLoopback::BladerfLbRfLna1 => LmsLna::Lna1,
+                    BladerfLoopback::BladerfLbRfLna2 => LmsLna::Lna2,
+                    _ => LmsLna::Lna3,
+                };
+
+                /* Power down LNAs */
+                self.select_lna(LmsLna::LnaNone)?;
+
+                /* Ensure RXVGA1 is enabled */
+                self.rxvga1_enable(true)?;
+
+                /* Enable the RXLPF if needed */
+                if lpf_mode == BladerfLpfMode::BladerfLpfDisabled {
+                    self.lpf_set_mode(BLADERF_MODULE_RX, BladerfLpfMode::BladerfLpfNormal)?;
+                }
+
+                /* Ensure RXVGA2 is enabled */
+                self.rxvga2_enable(true)?;
+
+                /* Select output buffer in RX PLL and select the desired LNA */
+                let mut regval = self.read(0x25)?;
+                regval &= !0x03;
+                regval |= lna as u8;
+                self.write(0x25, regval)?;
+
+                self.select_lna(lna)?;
+            }
+            _ => return Err(anyhow!("Invalid loopback mode: {:?}", mode)),
+        }
+
+        Ok(())
+    }
+
+    /* Configure the TX side of the loopback path */
+    fn loopback_tx(&self, mode: BladerfLoopback) -> Result<()> {
+        match mode {
+            BladerfLoopback::BladerfLbNone => {
+                /* Restore proper settings (PA) for this frequency */
+                let f = self.get_frequency(BLADERF_MODULE_TX)?;
+                if Self::frequency_to_hz(&f) < BLADERF1_BAND_HIGH {
+                    self.select_pa(LmsPa::Pa1)?;
+                } else {
+                    self.select_pa(LmsPa::Pa2)?;
+                }
+            }
+            BladerfLoopback::BladerfLbBbTxlpfRxvga2
+            | BladerfLoopback::BladerfLbBbTxvga1Rxvga2
+            | BladerfLoopback::BladerfLbBbTxlpfRxlpf
+            | BladerfLoopback::BladerfLbBbTxvga1Rxlpf => {}
+            BladerfLoopback::BladerfLbRfLna1
+            | BladerfLoopback::BladerfLbRfLna2
+            | BladerfLoopback::BladerfLbRfLna3 => {
+                self.select_pa(LmsPa::PaAux)?;
+            }
+            _ => return Err(anyhow!("Invalid loopback mode: {:?}", mode)),
+        }
+
+        Ok(())
+    }
+
+    /// Configure the LMS6002D internal loopback paths. `BladerfLbNone` restores
+    /// normal operation, selecting the PA and LNA for the current frequencies.
+    pub fn set_loopback_mode(&self, mode: BladerfLoopback) -> Result<()> {
+        /* Verify a valid mode is provided before shutting anything down */
+        match mode {
+            BladerfLoopback::BladerfLbNone
+            | BladerfLoopback::BladerfLbBbTxlpfRxvga2
+            | BladerfLoopback::BladerfLbBbTxvga1Rxvga2
+            | BladerfLoopback::BladerfLbBbTxlpfRxlpf
+            | BladerfLoopback::BladerfLbBbTxvga1Rxlpf
+            | BladerfLoopback::BladerfLbRfLna1
+            | BladerfLoopback::BladerfLbRfLna2
+            | BladerfLoopback::BladerfLbRfLna3 => {}
+            _ => return Err(anyhow!("Invalid loopback mode: {:?}", mode)),
+        }
+
+        /* Disable all PA/LNAs while entering loopback mode or making changes */
+        self.select_pa(LmsPa::PaNone)?;
+        self.select_lna(LmsLna::LnaNone)?;
+
+        /* Disconnect loopback paths while we re-configure blocks */
+        self.loopback_path(BladerfLoopback::BladerfLbNone)?;
+
+        /* Configure the RX side of the loopback path */
+        self.loopback_rx(mode)?;
+
+        /* Configure the TX side of the path */
+        self.loopback_tx(mode)?;
+
+        /* Configure "switches" along the loopback path */
+        self.loopback_path(mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_round_trip() {
+        let snapshot = LmsRegisterSnapshot {
+            regs: LMS_REG_DUMPSET
+                .iter()
+                .map(|&addr| (addr, addr.wrapping_mul(7)))
+                .collect(),
+        };
+
+        let text = snapshot.to_string();
+        assert!(text.starts_with("{\n  \"0x00\": \"0x00\",\n  \"0x01\": \"0x07\","));
+        assert!(text.ends_with("  \"0x7c\": \"0x64\"\n}"));
+
+        let parsed: LmsRegisterSnapshot = text.parse().unwrap();
+        assert_eq!(parsed, snapshot);
+        assert_eq!(parsed.get(0x7c), Some(0x64));
+        assert_eq!(parsed.get(0x0c), None);
+
+        /* Upper case hex, missing prefixes and surrounding whitespace are accepted */
+        let parsed: LmsRegisterSnapshot = " { \"0X4A\": \"ff\" } ".parse().unwrap();
+        assert_eq!(parsed.registers(), &[(0x4a, 0xff)]);
+
+        let empty: LmsRegisterSnapshot = "{}".parse().unwrap();
+        assert!(empty.registers().is_empty());
+    }
+
+    #[test]
+    fn snapshot_rejects_invalid_input() {
+        let parse = |s: &str| s.parse::<LmsRegisterSnapshot>();
+
+        /* Missing braces */
+        assert!(parse("\"0x00\": \"0x1f\"").is_err());
+        assert!(parse("{ \"0x00\": \"0x1f\"").is_err());
+        assert!(parse("\"0x00\": \"0x1f\" }").is_err());
+
+        /* Addresses outside of LMS_REG_DUMPSET */
+        assert!(parse("{ \"0x0c\": \"0x00\" }").is_err());
+        assert!(parse("{ \"0x80\": \"0x00\" }").is_err());
+
+        /* Bad hex, out of range values and missing separators */
+        assert!(parse("{ \"0x00\": \"0xzz\" }").is_err());
+        assert!(parse("{ \"0x00\": \"0x100\" }").is_err());
+        assert!(parse("{ \"0xg0\": \"0x00\" }").is_err());
+        assert!(parse("{ \"0x00\" \"0x00\" }").is_err());
+    }
 }
@@ -1,10 +1,12 @@
-use anyhow::anyhow;
 use crate::bladerf::BladerfRationalRate;
-use crate::board::bladerf1::{BLADERF_SAMPLERATE_MIN, BLADERF_SMB_FREQUENCY_MAX, BLADERF_SMB_FREQUENCY_MIN};
+use crate::board::bladerf1::{
+    BLADERF_SAMPLERATE_MIN, BLADERF_SMB_FREQUENCY_MAX, BLADERF_SMB_FREQUENCY_MIN,
+};
+use crate::nios::batch::NiosBatch;
 use crate::nios::constants::{NIOS_PKT_8X8_TARGET_SI5338, NIOS_PKT_FLAG_READ, NIOS_PKT_FLAG_WRITE};
-use crate::nios::packet8x8::NiosPacket8x8;
-use crate::nios::Nios;
-use nusb::Interface;
+use crate::nios::packet::NiosPacket8x8;
+use crate::nios::{Nios, NiosInterface};
+use anyhow::anyhow;
 
 use crate::bladerf_channel_rx;
 use crate::bladerf_channel_tx;
@@ -16,20 +18,25 @@ const SI5338_F_VCO: u64 = 38400000 * 66;
 const SI5338_EN_A: u8 = 0x01;
 const SI5338_EN_B: u8 = 0x02;
 
+/* Number of registers on page 0, excluding the page select register (255) */
+pub const SI5338_NUM_REGISTERS: usize = 255;
+
+/* Multisynths 0-3 drive CLK0-CLK3 */
+pub const SI5338_NUM_MULTISYNTHS: usize = 4;
+
 /**
  * This is used set or recreate the si5338 frequency
  * Each si5338 multisynth module can be set independently
  */
 #[derive(Clone, Default)]
-struct Si5338Multisynth {
+pub struct Si5338Multisynth {
     /* Multisynth to program (0-3) */
     index: u8,
 
     /* Base address of the multisynth */
     base: u16,
 
-    /* Requested and actual sample rates */
-    requested: BladerfRationalRate,
+    /* Actual sample rate */
     actual: BladerfRationalRate,
 
     /* Enables for A and/or B outputs */
@@ -50,12 +57,67 @@ struct Si5338Multisynth {
     regs: [u8; 10],
 }
 
+impl Si5338Multisynth {
+    /// Multisynth index (0-3).
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Output frequency, as computed from the (a, b, c, r) values.
+    pub fn frequency(&self) -> &BladerfRationalRate {
+        &self.actual
+    }
+
+    /// Enable bits for the A and/or B outputs.
+    pub fn enable(&self) -> u8 {
+        self.enable
+    }
+}
+
+impl std::fmt::Display for Si5338Multisynth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "MS{} (base 0x{:02x}):", self.index, self.base)?;
+        writeln!(
+            f,
+            "  outputs:  {}{}",
+            if self.enable & SI5338_EN_A != 0 {
+                "A "
+            } else {
+                ""
+            },
+            if self.enable & SI5338_EN_B != 0 {
+                "B"
+            } else {
+                ""
+            }
+        )?;
+        writeln!(f, "  a + b/c:  {} + {}/{}", self.a, self.b, self.c)?;
+        writeln!(f, "  r:        {}", self.r)?;
+        writeln!(f, "  p1/p2/p3: {} / {} / {}", self.p1, self.p2, self.p3)?;
+        writeln!(
+            f,
+            "  regs:     {}",
+            self.regs
+                .iter()
+                .map(|reg| format!("{:02x}", reg))
+                .collect::<Vec<String>>()
+                .join(" ")
+        )?;
+        write!(
+            f,
+            "  freq:     {} Hz ({})",
+            self.actual.as_f64(),
+            self.actual
+        )
+    }
+}
+
 pub struct SI5338 {
-    interface: Interface,
+    interface: NiosInterface,
 }
 
 impl SI5338 {
-    pub fn new(interface: Interface) -> Self {
+    pub fn new(interface: NiosInterface) -> Self {
         Self { interface }
     }
     pub fn read(&self, addr: u8) -> anyhow::Result<u8> {
@@ -65,7 +127,7 @@ impl SI5338 {
         let response = self
             .interface
             .nios_send(ENDPOINT_IN, ENDPOINT_OUT, request.into_vec())?;
-        Ok(NiosPacket8x8::reuse(response).data())
+        Ok(NiosPacket8x8::from_vec(response)?.data())
     }
 
     pub fn write(&self, addr: u8, data: u8) -> anyhow::Result<u8> {
@@ -75,7 +137,39 @@ impl SI5338 {
         let response = self
             .interface
             .nios_send(ENDPOINT_IN, ENDPOINT_OUT, request.into_vec())?;
-        Ok(NiosPacket8x8::reuse(response).data())
+        Ok(NiosPacket8x8::from_vec(response)?.data())
+    }
+
+    /// Read every register on page 0 of the part.
+    pub fn dump_registers(&self) -> anyhow::Result<[u8; SI5338_NUM_REGISTERS]> {
+        let mut regs = [0u8; SI5338_NUM_REGISTERS];
+        for (addr, reg) in regs.iter_mut().enumerate() {
+            *reg = self.read(addr as u8)?;
+        }
+        Ok(regs)
+    }
+
+    /// Read back and decode the configuration of every multisynth.
+    pub fn dump_multisynths(&self) -> anyhow::Result<Vec<Si5338Multisynth>> {
+        (0..SI5338_NUM_MULTISYNTHS as u8)
+            .map(|index| {
+                let mut ms = Si5338Multisynth {
+                    index,
+                    ..Default::default()
+                };
+                Self::update_base(&mut ms);
+                self.read_multisynth(&mut ms)?;
+
+                /* An unprogrammed multisynth has c = 0 */
+                if ms.c != 0 {
+                    let mut actual = BladerfRationalRate::default();
+                    Self::calculate_ms_freq(&mut ms, &mut actual);
+                    ms.actual = actual;
+                }
+
+                Ok(ms)
+            })
+            .collect()
     }
 
     /**
@@ -265,13 +359,18 @@ impl SI5338 {
     pub fn write_multisynth(&self, ms: &Si5338Multisynth) -> anyhow::Result<u8> {
         let mut val = self.read(36 + ms.index)?;
         val |= ms.enable;
-        println!("Wrote enable register: {:x}", val);
-        self.write(36 + ms.index, val)?;
+
+        /* The remaining writes don't depend on each other, so send them as one batch */
+        let mut batch = NiosBatch::new();
+        batch.write(NIOS_PKT_8X8_TARGET_SI5338, 36 + ms.index, val);
 
         /* Write out the registers */
         for i in 0..ms.regs.len() {
-            self.write((ms.base + i as u16) as u8, ms.regs[i])?;
-            println!("Wrote regs[{}]: {}", i, ms.regs[i]);
+            batch.write(
+                NIOS_PKT_8X8_TARGET_SI5338,
+                (ms.base + i as u16) as u8,
+                ms.regs[i],
+            );
         }
 
         /* Calculate r_power from c_count */
@@ -286,12 +385,15 @@ impl SI5338 {
         val = 0xc0;
         val |= r_power << 2;
 
-        println!("Wrote r register: {:x}", val);
+        let r_idx = batch.write(NIOS_PKT_8X8_TARGET_SI5338, ms.index + 31, val);
 
-        Ok(self.write(ms.index + 31, val)?)
+        batch.submit(&self.interface)?.data::<u8, u8>(r_idx)
     }
 
-    pub fn calculate_multisynth(ms: &mut Si5338Multisynth, rate: &BladerfRationalRate) {
+    pub fn calculate_multisynth(
+        ms: &mut Si5338Multisynth,
+        rate: &BladerfRationalRate,
+    ) -> anyhow::Result<()> {
         let mut r_value: u8;
 
         /* Don't mess with the users data */
@@ -315,7 +417,11 @@ impl SI5338 {
             r_value <<= 1;
         }
 
-        assert!(!(r_value == 32 && req.integer < 5000000));
+        if r_value == 32 && req.integer < 5000000 {
+            return Err(anyhow!(
+                "Requested rate is too low for the multisynth R divider"
+            ));
+        }
 
         /* Find suitable MS (a, b, c) values */
         let mut abc = BladerfRationalRate {
@@ -328,15 +434,11 @@ impl SI5338 {
         // log_verbose("MSx a + b/c: %"PRIu64" + %"PRIu64"/%"PRIu64"\n", abc.integer, abc.num, abc.den);
 
         /* Check values to make sure they are OK */
-        assert!(abc.integer > 7);
-        assert!(abc.integer < 568);
-        // if abc.integer < 8 {
-        //     //log_debug("Integer portion too small: %"PRIu64"\n", abc.integer);
-        //     return BLADERF_ERR_INVAL;
-        // } else if abc.integer > 567 {
-        //     // log_debug("Integer portion too large: %"PRIu64"\n", abc.integer);
-        //     return BLADERF_ERR_INVAL;
-        // }
+        if abc.integer < 8 {
+            return Err(anyhow!("Integer portion too small: {}", abc.integer));
+        } else if abc.integer > 567 {
+            return Err(anyhow!("Integer portion too large: {}", abc.integer));
+        }
 
         /* Loss of precision if num or den are greater than 2^30-1 */
         while abc.num > (1 << 30) || abc.den > (1 << 30) {
@@ -358,6 +460,7 @@ impl SI5338 {
 
         /* Pack the registers */
         Self::pack_regs(ms);
+        Ok(())
     }
 
     pub fn set_rational_multisynth(
@@ -383,7 +486,7 @@ impl SI5338 {
         Self::update_base(&mut ms);
 
         /* Calculate multisynth values */
-        Self::calculate_multisynth(&mut ms, &rate);
+        Self::calculate_multisynth(&mut ms, &rate)?;
 
         /* Get the actual rate */
         Self::calculate_ms_freq(&mut ms, &mut actual);
@@ -420,6 +523,34 @@ impl SI5338 {
         Ok(self.set_rational_multisynth(index, channel, rate_reduced)?)
     }
 
+    /// Calculate the exact rate the sample clock multisynth of a channel would produce for
+    /// the requested rate, without programming the part.
+    pub fn calculate_sample_rate(
+        ch: u8,
+        rate: &BladerfRationalRate,
+    ) -> anyhow::Result<BladerfRationalRate> {
+        let mut ms = Si5338Multisynth::default();
+        let mut rate_reduced = rate.clone();
+        let mut actual = BladerfRationalRate::default();
+
+        Self::rational_reduce(&mut rate_reduced);
+        if rate_reduced.integer < BLADERF_SAMPLERATE_MIN {
+            return Err(anyhow!(
+                "Sample rate {} is below the minimum of {}",
+                rate_reduced,
+                BLADERF_SAMPLERATE_MIN
+            ));
+        }
+
+        ms.index = if ch == bladerf_channel_rx!(0) { 1 } else { 2 };
+        Self::update_base(&mut ms);
+
+        Self::calculate_multisynth(&mut ms, &rate_reduced)?;
+        Self::calculate_ms_freq(&mut ms, &mut actual);
+
+        Ok(actual)
+    }
+
     pub fn set_sample_rate(&self, channel: u8, rate_requested: u32) -> anyhow::Result<u32> {
         let mut req = BladerfRationalRate {
             integer: rate_requested as u64,
@@ -470,22 +601,25 @@ impl SI5338 {
         Ok(actual.integer as u32)
     }
 
-    pub fn set_rational_smb_freq(&self, rate: &BladerfRationalRate) -> anyhow::Result<BladerfRationalRate> {
+    pub fn set_rational_smb_freq(
+        &self,
+        rate: &BladerfRationalRate,
+    ) -> anyhow::Result<BladerfRationalRate> {
         let mut rate_reduced = rate.clone();
 
         /* Enforce minimum and maximum frequencies */
         Self::rational_reduce(&mut rate_reduced);
 
         if rate_reduced.integer < BLADERF_SMB_FREQUENCY_MIN as u64 {
-            return Err(anyhow!("provided SMB freq violates minimum"))
+            return Err(anyhow!("provided SMB freq violates minimum"));
         } else if rate_reduced.integer > BLADERF_SMB_FREQUENCY_MAX as u64 {
-            return Err(anyhow!("provided SMB freq violates maximum"))
+            return Err(anyhow!("provided SMB freq violates maximum"));
         }
 
-        Ok(self.set_rational_multisynth(3, SI5338_EN_A, rate_reduced)?)
+        self.set_rational_multisynth(3, SI5338_EN_A, rate_reduced)
     }
 
-    pub fn set_smb_freq(&self, rate: u32)-> anyhow::Result<u32> {
+    pub fn set_smb_freq(&self, rate: u32) -> anyhow::Result<u32> {
         let mut req = BladerfRationalRate::default();
         println!("Setting integer SMB frequency: {}", rate);
         req.integer = rate as u64;
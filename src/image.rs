@@ -0,0 +1,246 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/*
+ * Container format of the images written by bladeRF-cli and libbladeRF
+ * (firmware, FPGA bitstreams and calibration tables). All header fields are
+ * big-endian.
+ *
+ *  Offset  Length  Field
+ *  0x0000       7  Magic ("bladeRF", not NUL-terminated)
+ *  0x0007      32  SHA256 checksum of the image, with this field zeroed
+ *  0x0027       6  Image format version (u16 major, minor, patch)
+ *  0x002d       8  Timestamp (seconds since the epoch)
+ *  0x0035      33  Serial number of the device (NUL-terminated)
+ *  0x0056     128  Reserved
+ *  0x00d6       4  Image type
+ *  0x00da       4  Address
+ *  0x00de       4  Length of the data
+ *  0x00e2       -  Data
+ */
+const BLADERF_IMAGE_MAGIC: &[u8; 7] = b"bladeRF";
+const BLADERF_IMAGE_CHECKSUM_LEN: usize = 32;
+const BLADERF_IMAGE_RESERVED_LEN: usize = 128;
+const BLADERF_SERIAL_LENGTH: usize = 33;
+
+const CHECKSUM_OFFSET: usize = BLADERF_IMAGE_MAGIC.len();
+const VERSION_OFFSET: usize = CHECKSUM_OFFSET + BLADERF_IMAGE_CHECKSUM_LEN;
+const TIMESTAMP_OFFSET: usize = VERSION_OFFSET + 3 * size_of::<u16>();
+const SERIAL_OFFSET: usize = TIMESTAMP_OFFSET + size_of::<u64>();
+const TYPE_OFFSET: usize = SERIAL_OFFSET + BLADERF_SERIAL_LENGTH + BLADERF_IMAGE_RESERVED_LEN;
+const ADDRESS_OFFSET: usize = TYPE_OFFSET + size_of::<u32>();
+const LENGTH_OFFSET: usize = ADDRESS_OFFSET + size_of::<u32>();
+const DATA_OFFSET: usize = LENGTH_OFFSET + size_of::<u32>();
+
+/* Image format version written by libbladeRF */
+const BLADERF_IMAGE_VERSION: (u16, u16, u16) = (0, 1, 0);
+
+/**
+ * Type of the data contained in an image
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum BladerfImageType {
+    /* Misc. raw data */
+    Raw = 0,
+    /* Firmware data */
+    Firmware = 1,
+    /* FPGA bitstream for 40 KLE device */
+    Fpga40kle = 2,
+    /* FPGA bitstream for 115 KLE device */
+    Fpga115kle = 3,
+    /* Board calibration */
+    Calibration = 4,
+    /* RX DC offset calibration table */
+    RxDcCal = 5,
+    /* TX DC offset calibration table */
+    TxDcCal = 6,
+    /* RX IQ balance calibration table */
+    RxIqCal = 7,
+    /* TX IQ balance calibration table */
+    TxIqCal = 8,
+    /* FPGA bitstream for A4 device */
+    FpgaA4 = 9,
+    /* FPGA bitstream for A9 device */
+    FpgaA9 = 10,
+    /* FPGA bitstream for A5 device */
+    FpgaA5 = 11,
+}
+
+impl TryFrom<u32> for BladerfImageType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self> {
+        Ok(match value {
+            0 => Self::Raw,
+            1 => Self::Firmware,
+            2 => Self::Fpga40kle,
+            3 => Self::Fpga115kle,
+            4 => Self::Calibration,
+            5 => Self::RxDcCal,
+            6 => Self::TxDcCal,
+            7 => Self::RxIqCal,
+            8 => Self::TxIqCal,
+            9 => Self::FpgaA4,
+            10 => Self::FpgaA9,
+            11 => Self::FpgaA5,
+            _ => return Err(anyhow!("Invalid image type: {}", value)),
+        })
+    }
+}
+
+/**
+ * A bladeRF image, as produced by `bladeRF-cli` (e.g. `cal table dc rx`)
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct BladerfImage {
+    /// Image format version (major, minor, patch)
+    pub version: (u16, u16, u16),
+    /// Creation time, in seconds since the epoch
+    pub timestamp: u64,
+    /// Serial number of the device the image was created for
+    pub serial: String,
+    pub image_type: BladerfImageType,
+    /// Address the data is intended for, if it is to be written to flash
+    pub address: u32,
+    pub data: Vec<u8>,
+}
+
+impl BladerfImage {
+    pub fn new(image_type: BladerfImageType, address: u32, data: Vec<u8>) -> Self {
+        Self {
+            version: BLADERF_IMAGE_VERSION,
+            timestamp: 0,
+            serial: String::new(),
+            image_type,
+            address,
+            data,
+        }
+    }
+
+    /// Read and verify an image file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Parse an image, verifying its magic and checksum.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self> {
+        if buf.len() < DATA_OFFSET {
+            return Err(anyhow!(
+                "Image too short: {} bytes, header is {} bytes",
+                buf.len(),
+                DATA_OFFSET
+            ));
+        }
+
+        if &buf[..CHECKSUM_OFFSET] != BLADERF_IMAGE_MAGIC {
+            return Err(anyhow!("Invalid image magic"));
+        }
+
+        let mut unchecked = buf.to_vec();
+        unchecked[CHECKSUM_OFFSET..VERSION_OFFSET].fill(0);
+        if sha256(&unchecked)[..] != buf[CHECKSUM_OFFSET..VERSION_OFFSET] {
+            return Err(anyhow!("Image checksum mismatch"));
+        }
+
+        let be_u16 = |offset: usize| u16::from_be_bytes([buf[offset], buf[offset + 1]]);
+        let be_u32 = |offset: usize| {
+            u32::from_be_bytes(buf[offset..offset + 4].try_into().unwrap_or_default())
+        };
+
+        let length = be_u32(LENGTH_OFFSET) as usize;
+        let data = buf[DATA_OFFSET..].get(..length).ok_or_else(|| {
+            anyhow!(
+                "Image data truncated: expected {} bytes, got {}",
+                length,
+                buf.len() - DATA_OFFSET
+            )
+        })?;
+
+        let serial = &buf[SERIAL_OFFSET..SERIAL_OFFSET + BLADERF_SERIAL_LENGTH];
+        let serial_len = serial.iter().position(|&b| b == 0).unwrap_or(serial.len());
+
+        Ok(Self {
+            version: (
+                be_u16(VERSION_OFFSET),
+                be_u16(VERSION_OFFSET + 2),
+                be_u16(VERSION_OFFSET + 4),
+            ),
+            timestamp: u64::from_be_bytes(
+                buf[TIMESTAMP_OFFSET..SERIAL_OFFSET]
+                    .try_into()
+                    .unwrap_or_default(),
+            ),
+            serial: String::from_utf8_lossy(&serial[..serial_len]).into_owned(),
+            image_type: BladerfImageType::try_from(be_u32(TYPE_OFFSET))?,
+            address: be_u32(ADDRESS_OFFSET),
+            data: data.to_vec(),
+        })
+    }
+
+    /// Serialize the image, including its checksum.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; DATA_OFFSET];
+        buf[..CHECKSUM_OFFSET].copy_from_slice(BLADERF_IMAGE_MAGIC);
+        buf[VERSION_OFFSET..VERSION_OFFSET + 2].copy_from_slice(&self.version.0.to_be_bytes());
+        buf[VERSION_OFFSET + 2..VERSION_OFFSET + 4].copy_from_slice(&self.version.1.to_be_bytes());
+        buf[VERSION_OFFSET + 4..TIMESTAMP_OFFSET].copy_from_slice(&self.version.2.to_be_bytes());
+        buf[TIMESTAMP_OFFSET..SERIAL_OFFSET].copy_from_slice(&self.timestamp.to_be_bytes());
+
+        /* Leave room for the NUL terminator */
+        let serial = self.serial.as_bytes();
+        let serial_len = serial.len().min(BLADERF_SERIAL_LENGTH - 1);
+        buf[SERIAL_OFFSET..SERIAL_OFFSET + serial_len].copy_from_slice(&serial[..serial_len]);
+
+        buf[TYPE_OFFSET..ADDRESS_OFFSET].copy_from_slice(&(self.image_type as u32).to_be_bytes());
+        buf[ADDRESS_OFFSET..LENGTH_OFFSET].copy_from_slice(&self.address.to_be_bytes());
+        buf[LENGTH_OFFSET..DATA_OFFSET].copy_from_slice(&(self.data.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&self.data);
+
+        let checksum = sha256(&buf);
+        buf[CHECKSUM_OFFSET..VERSION_OFFSET].copy_from_slice(&checksum);
+        buf
+    }
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_round_trip() {
+        let mut image = BladerfImage::new(BladerfImageType::RxDcCal, 0x1234, vec![1, 2, 3, 4, 5]);
+        image.timestamp = 1_500_000_000;
+        image.serial = "f12ce1037830a1b27f3ceeba1f521413".to_string();
+
+        let buf = image.to_bytes();
+        assert_eq!(buf.len(), DATA_OFFSET + 5);
+        assert_eq!(DATA_OFFSET, 0xe2);
+        assert_eq!(&buf[..7], b"bladeRF");
+        assert_eq!(&buf[TYPE_OFFSET..ADDRESS_OFFSET], &[0, 0, 0, 5]);
+
+        assert_eq!(BladerfImage::from_bytes(&buf).unwrap(), image);
+    }
+
+    #[test]
+    fn image_rejects_corruption() {
+        let image = BladerfImage::new(BladerfImageType::TxDcCal, 0, vec![0xaa; 16]);
+        let buf = image.to_bytes();
+
+        let mut corrupted = buf.clone();
+        corrupted[DATA_OFFSET + 3] ^= 0x01;
+        assert!(BladerfImage::from_bytes(&corrupted).is_err());
+
+        let mut bad_magic = buf.clone();
+        bad_magic[0] = b'B';
+        assert!(BladerfImage::from_bytes(&bad_magic).is_err());
+
+        assert!(BladerfImage::from_bytes(&buf[..DATA_OFFSET - 1]).is_err());
+        assert!(BladerfImage::from_bytes(&buf[..buf.len() - 1]).is_err());
+    }
+}
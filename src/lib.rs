@@ -50,6 +50,7 @@
 pub mod bladerf;
 pub mod board;
 pub mod hardware;
+pub mod image;
 pub mod nios;
 mod types;
 mod usb;
@@ -1,21 +1,39 @@
-use crate::nios::packet_generic::NiosPacket;
+use crate::nios::packet::nios_pkt_success;
 use anyhow::anyhow;
 use futures_lite::future::block_on;
-use nusb::transfer::RequestBuffer;
+use nusb::transfer::{Queue, RequestBuffer};
 use nusb::Interface;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+pub mod access;
+pub mod batch;
+pub mod bus;
 pub mod constants;
-pub mod packet16x64;
-pub mod packet32x32;
-pub mod packet8x16;
-pub mod packet8x32;
-pub mod packet8x64;
-pub mod packet8x8;
-mod packet_generic;
+pub mod constants_legacy;
+pub mod legacy;
+pub mod packet;
+pub mod packet_legacy;
+
+/* Maximum number of batched requests awaiting a response */
+const NIOS_BATCH_DEPTH: usize = 8;
 
 pub trait Nios {
     fn nios_send(&self, endpoint_in: u8, endpoint_out: u8, pkt: Vec<u8>)
         -> anyhow::Result<Vec<u8>>;
+
+    /// Send a sequence of requests, returning the responses in request order.
+    /// The default implementation sends one request at a time.
+    fn nios_send_batch(
+        &self,
+        endpoint_in: u8,
+        endpoint_out: u8,
+        pkts: Vec<Vec<u8>>,
+    ) -> anyhow::Result<Vec<Vec<u8>>> {
+        pkts.into_iter()
+            .map(|pkt| self.nios_send(endpoint_in, endpoint_out, pkt))
+            .collect()
+    }
 }
 impl Nios for Interface {
     fn nios_send(
@@ -24,19 +42,168 @@ impl Nios for Interface {
         endpoint_out: u8,
         pkt: Vec<u8>,
     ) -> anyhow::Result<Vec<u8>> {
-        println!("BulkOut: {:x?}", pkt);
+        log::trace!("BulkOut: {:x?}", pkt);
         let response = block_on(self.bulk_out(endpoint_out, pkt)).into_result()?;
 
         let response =
             block_on(self.bulk_in(endpoint_in, RequestBuffer::reuse(response.reuse(), 16)))
                 .into_result()?;
 
-        let nios_pkt = NiosPacket::from_vec(response);
-        if !nios_pkt.success() {
+        if !nios_pkt_success(&response) {
             return Err(anyhow!("operation was unsuccessful!"));
         }
-        let response_vec = nios_pkt.into_vec();
-        println!("BulkIn:  {:x?}", response_vec);
-        Ok(response_vec)
+        log::trace!("BulkIn:  {:x?}", response);
+        Ok(response)
+    }
+
+    /* Keep up to NIOS_BATCH_DEPTH requests in flight. The FPGA handles requests
+     * in order, so responses complete in the order the requests were sent. */
+    fn nios_send_batch(
+        &self,
+        endpoint_in: u8,
+        endpoint_out: u8,
+        pkts: Vec<Vec<u8>>,
+    ) -> anyhow::Result<Vec<Vec<u8>>> {
+        let mut out_queue = self.bulk_out_queue(endpoint_out);
+        let mut in_queue = self.bulk_in_queue(endpoint_in);
+        let count = pkts.len();
+        let mut responses = Vec::with_capacity(count);
+        let mut pending = pkts.into_iter();
+
+        while responses.len() < count {
+            while in_queue.pending() < NIOS_BATCH_DEPTH {
+                let Some(pkt) = pending.next() else {
+                    break;
+                };
+                log::trace!("BulkOut: {:x?}", pkt);
+                in_queue.submit(RequestBuffer::new(16));
+                out_queue.submit(pkt);
+            }
+
+            if let Err(e) = block_on(out_queue.next_complete()).into_result() {
+                drain_batch(&mut out_queue, &mut in_queue);
+                return Err(e.into());
+            }
+
+            let result = block_on(in_queue.next_complete()).into_result();
+            let response = match result {
+                Ok(response) if nios_pkt_success(&response) => response,
+                Ok(_) => {
+                    drain_batch(&mut out_queue, &mut in_queue);
+                    return Err(anyhow!(
+                        "operation {} of batch was unsuccessful!",
+                        responses.len()
+                    ));
+                }
+                Err(e) => {
+                    drain_batch(&mut out_queue, &mut in_queue);
+                    return Err(e.into());
+                }
+            };
+            log::trace!("BulkIn:  {:x?}", response);
+            responses.push(response);
+        }
+
+        Ok(responses)
+    }
+}
+
+/*
+ * Abort a batch after a failed request. The FPGA still answers every request
+ * that reached it, so consume those responses before cancelling the remaining
+ * IN transfers. Otherwise the next transaction would read a stale response.
+ */
+fn drain_batch(out_queue: &mut Queue<Vec<u8>>, in_queue: &mut Queue<RequestBuffer>) {
+    let mut delivered = 0;
+    while out_queue.pending() > 0 {
+        if block_on(out_queue.next_complete()).into_result().is_ok() {
+            delivered += 1;
+        }
+    }
+
+    /* Responses complete the oldest IN transfers first, whichever request they
+     * belong to, so only the number of delivered requests matters */
+    for _ in 0..delivered.min(in_queue.pending()) {
+        let _ = block_on(in_queue.next_complete());
+    }
+
+    in_queue.cancel_all();
+    while in_queue.pending() > 0 {
+        let _ = block_on(in_queue.next_complete());
+    }
+}
+
+/**
+ * NIOS II access through the FX3, using either the AxB or the legacy packet format.
+ *
+ * FPGA images older than v0.3.0 only implement the legacy 'N' packet handler. In
+ * legacy mode, AxB requests are carried out through legacy accesses and the AxB
+ * response is synthesized, so drivers only need to deal with the AxB formats.
+ */
+#[derive(Clone)]
+pub struct NiosInterface {
+    interface: Interface,
+    legacy: Arc<AtomicBool>,
+}
+
+impl NiosInterface {
+    pub fn new(interface: Interface) -> Self {
+        Self {
+            interface,
+            legacy: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Select the legacy packet format for all clones of this interface.
+    pub fn set_legacy(&self, legacy: bool) {
+        self.legacy.store(legacy, Ordering::Relaxed);
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.legacy.load(Ordering::Relaxed)
+    }
+
+    /// Send an AxB request through the legacy packet handler, regardless of the selected
+    /// format. Every FPGA image implements the legacy handler.
+    pub fn legacy_send(
+        &self,
+        endpoint_in: u8,
+        endpoint_out: u8,
+        pkt: Vec<u8>,
+    ) -> anyhow::Result<Vec<u8>> {
+        legacy::legacy_send(&self.interface, endpoint_in, endpoint_out, pkt)
+    }
+}
+
+impl Nios for NiosInterface {
+    fn nios_send(
+        &self,
+        endpoint_in: u8,
+        endpoint_out: u8,
+        pkt: Vec<u8>,
+    ) -> anyhow::Result<Vec<u8>> {
+        if self.is_legacy() {
+            self.legacy_send(endpoint_in, endpoint_out, pkt)
+        } else {
+            self.interface.nios_send(endpoint_in, endpoint_out, pkt)
+        }
+    }
+
+    fn nios_send_batch(
+        &self,
+        endpoint_in: u8,
+        endpoint_out: u8,
+        pkts: Vec<Vec<u8>>,
+    ) -> anyhow::Result<Vec<Vec<u8>>> {
+        /* The legacy handler only processes one access per packet, so batches are
+         * sent one request at a time */
+        if self.is_legacy() {
+            pkts.into_iter()
+                .map(|pkt| self.legacy_send(endpoint_in, endpoint_out, pkt))
+                .collect()
+        } else {
+            self.interface
+                .nios_send_batch(endpoint_in, endpoint_out, pkts)
+        }
     }
 }
@@ -0,0 +1,59 @@
+use crate::nios::constants::{NIOS_PKT_FLAG_READ, NIOS_PKT_FLAG_WRITE};
+use crate::nios::packet::{NiosDecode, NiosEncode, NiosPacket, NiosPacketFormat};
+use crate::nios::Nios;
+use anyhow::Result;
+
+const PERIPHERAL_ENDPOINT_OUT: u8 = 0x02;
+const PERIPHERAL_ENDPOINT_IN: u8 = 0x82;
+
+/**
+ * NIOS packet format, selected by the (address, data) type pair.
+ *
+ * Implemented for every pair with a NiosPacketFormat: (u8, u8), (u8, u16),
+ * (u8, u32), (u8, u64), (u16, u64) and (u32, u32), matching the 8x8, 8x16,
+ * 8x32, 8x64, 16x64 and 32x32 packet handlers in the FPGA.
+ */
+pub trait NiosFormat {
+    type Addr;
+    type Data;
+
+    fn read<N: Nios>(interface: &N, target_id: u8, addr: Self::Addr) -> Result<Self::Data>;
+    fn write<N: Nios>(
+        interface: &N,
+        target_id: u8,
+        addr: Self::Addr,
+        data: Self::Data,
+    ) -> Result<()>;
+}
+
+impl<A, D> NiosFormat for (A, D)
+where
+    A: NiosEncode + NiosDecode,
+    D: NiosEncode + NiosDecode + Default,
+    (A, D): NiosPacketFormat,
+{
+    type Addr = A;
+    type Data = D;
+
+    fn read<N: Nios>(interface: &N, target_id: u8, addr: A) -> Result<D> {
+        let mut request = NiosPacket::<A, D>::new();
+        request.set(target_id, NIOS_PKT_FLAG_READ, addr, D::default());
+        let response = interface.nios_send(
+            PERIPHERAL_ENDPOINT_IN,
+            PERIPHERAL_ENDPOINT_OUT,
+            request.into_vec(),
+        )?;
+        Ok(NiosPacket::<A, D>::from_vec(response)?.data())
+    }
+
+    fn write<N: Nios>(interface: &N, target_id: u8, addr: A, data: D) -> Result<()> {
+        let mut request = NiosPacket::<A, D>::new();
+        request.set(target_id, NIOS_PKT_FLAG_WRITE, addr, data);
+        let _response = interface.nios_send(
+            PERIPHERAL_ENDPOINT_IN,
+            PERIPHERAL_ENDPOINT_OUT,
+            request.into_vec(),
+        )?;
+        Ok(())
+    }
+}
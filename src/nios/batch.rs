@@ -0,0 +1,104 @@
+use crate::nios::constants::{NIOS_PKT_FLAG_READ, NIOS_PKT_FLAG_WRITE};
+use crate::nios::packet::{NiosDecode, NiosEncode, NiosPacket, NiosPacketFormat};
+use crate::nios::Nios;
+use anyhow::{anyhow, Result};
+
+const PERIPHERAL_ENDPOINT_OUT: u8 = 0x02;
+const PERIPHERAL_ENDPOINT_IN: u8 = 0x82;
+
+/**
+ * A sequence of NIOS requests, submitted back-to-back instead of waiting for
+ * each response before sending the next request.
+ *
+ * Requests are carried out in the order they were queued. Each queued request
+ * returns an index, used to look up its response once the batch is submitted.
+ * Requests cannot depend on the result of an earlier request in the same
+ * batch, so read-modify-write sequences need their reads in a preceding batch.
+ */
+#[derive(Default)]
+pub struct NiosBatch {
+    requests: Vec<Vec<u8>>,
+}
+
+impl NiosBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a register read. Returns the index of its response.
+    pub fn read<A, D>(&mut self, target_id: u8, addr: A) -> usize
+    where
+        A: NiosEncode + NiosDecode,
+        D: NiosEncode + NiosDecode + Default,
+        (A, D): NiosPacketFormat,
+    {
+        let mut request = NiosPacket::<A, D>::new();
+        request.set(target_id, NIOS_PKT_FLAG_READ, addr, D::default());
+        self.push(request.into_vec())
+    }
+
+    /// Queue a register write. Returns the index of its response.
+    pub fn write<A, D>(&mut self, target_id: u8, addr: A, data: D) -> usize
+    where
+        A: NiosEncode + NiosDecode,
+        D: NiosEncode + NiosDecode,
+        (A, D): NiosPacketFormat,
+    {
+        let mut request = NiosPacket::<A, D>::new();
+        request.set(target_id, NIOS_PKT_FLAG_WRITE, addr, data);
+        self.push(request.into_vec())
+    }
+
+    fn push(&mut self, request: Vec<u8>) -> usize {
+        self.requests.push(request);
+        self.requests.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Send all queued requests and collect their responses. Fails on the first
+    /// unsuccessful request; requests after it may or may not have been carried out.
+    pub fn submit<N: Nios>(self, nios: &N) -> Result<NiosBatchResponses> {
+        let responses = nios.nios_send_batch(
+            PERIPHERAL_ENDPOINT_IN,
+            PERIPHERAL_ENDPOINT_OUT,
+            self.requests,
+        )?;
+        Ok(NiosBatchResponses { responses })
+    }
+}
+
+/// Responses of a submitted [`NiosBatch`], in request order.
+pub struct NiosBatchResponses {
+    responses: Vec<Vec<u8>>,
+}
+
+impl NiosBatchResponses {
+    /// The data field of the response to the request queued at `index`.
+    pub fn data<A, D>(&self, index: usize) -> Result<D>
+    where
+        A: NiosEncode + NiosDecode,
+        D: NiosEncode + NiosDecode,
+        (A, D): NiosPacketFormat,
+    {
+        let response = self
+            .responses
+            .get(index)
+            .ok_or_else(|| anyhow!("No response for batch request {}", index))?;
+        Ok(NiosPacket::<A, D>::from_bytes(response)?.data())
+    }
+
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+}
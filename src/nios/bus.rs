@@ -0,0 +1,81 @@
+use crate::nios::access::NiosFormat;
+use crate::nios::batch::NiosBatch;
+use crate::nios::constants::{NIOS_PKT_32X32_TARGET_ADI_AXI, NIOS_PKT_32X32_TARGET_WB_MSTR};
+use crate::nios::Nios;
+use anyhow::Result;
+
+/**
+ * Bus masters in the FPGA, reached through 32x32 NIOS packets.
+ *
+ * The ADI AXI interface provides access to the AD9361 core on the bladeRF 2.0,
+ * and the Wishbone master to peripherals on the FPGA's Wishbone bus. The batch
+ * variants perform a sequence of accesses in order, submitted back-to-back as a
+ * NiosBatch, and fail if any of the accesses fails.
+ */
+pub trait NiosBus {
+    fn wishbone_read(&self, addr: u32) -> Result<u32>;
+    fn wishbone_write(&self, addr: u32, data: u32) -> Result<()>;
+    fn wishbone_read_batch(&self, addrs: &[u32]) -> Result<Vec<u32>>;
+    fn wishbone_write_batch(&self, writes: &[(u32, u32)]) -> Result<()>;
+
+    fn adi_axi_read(&self, addr: u32) -> Result<u32>;
+    fn adi_axi_write(&self, addr: u32, data: u32) -> Result<()>;
+    fn adi_axi_read_batch(&self, addrs: &[u32]) -> Result<Vec<u32>>;
+    fn adi_axi_write_batch(&self, writes: &[(u32, u32)]) -> Result<()>;
+}
+
+fn bus_read_batch<N: Nios>(interface: &N, target_id: u8, addrs: &[u32]) -> Result<Vec<u32>> {
+    let mut batch = NiosBatch::new();
+    for &addr in addrs {
+        batch.read::<u32, u32>(target_id, addr);
+    }
+
+    let responses = batch.submit(interface)?;
+    (0..responses.len())
+        .map(|i| responses.data::<u32, u32>(i))
+        .collect()
+}
+
+fn bus_write_batch<N: Nios>(interface: &N, target_id: u8, writes: &[(u32, u32)]) -> Result<()> {
+    let mut batch = NiosBatch::new();
+    for &(addr, data) in writes {
+        batch.write(target_id, addr, data);
+    }
+
+    batch.submit(interface)?;
+    Ok(())
+}
+
+impl<N: Nios> NiosBus for N {
+    fn wishbone_read(&self, addr: u32) -> Result<u32> {
+        <(u32, u32)>::read(self, NIOS_PKT_32X32_TARGET_WB_MSTR, addr)
+    }
+
+    fn wishbone_write(&self, addr: u32, data: u32) -> Result<()> {
+        <(u32, u32)>::write(self, NIOS_PKT_32X32_TARGET_WB_MSTR, addr, data)
+    }
+
+    fn wishbone_read_batch(&self, addrs: &[u32]) -> Result<Vec<u32>> {
+        bus_read_batch(self, NIOS_PKT_32X32_TARGET_WB_MSTR, addrs)
+    }
+
+    fn wishbone_write_batch(&self, writes: &[(u32, u32)]) -> Result<()> {
+        bus_write_batch(self, NIOS_PKT_32X32_TARGET_WB_MSTR, writes)
+    }
+
+    fn adi_axi_read(&self, addr: u32) -> Result<u32> {
+        <(u32, u32)>::read(self, NIOS_PKT_32X32_TARGET_ADI_AXI, addr)
+    }
+
+    fn adi_axi_write(&self, addr: u32, data: u32) -> Result<()> {
+        <(u32, u32)>::write(self, NIOS_PKT_32X32_TARGET_ADI_AXI, addr, data)
+    }
+
+    fn adi_axi_read_batch(&self, addrs: &[u32]) -> Result<Vec<u32>> {
+        bus_read_batch(self, NIOS_PKT_32X32_TARGET_ADI_AXI, addrs)
+    }
+
+    fn adi_axi_write_batch(&self, writes: &[(u32, u32)]) -> Result<()> {
+        bus_write_batch(self, NIOS_PKT_32X32_TARGET_ADI_AXI, writes)
+    }
+}
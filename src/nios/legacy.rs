@@ -0,0 +1,421 @@
+use crate::nios::constants::{
+    NIOS_PKT_16X64_MAGIC, NIOS_PKT_32X32_MAGIC, NIOS_PKT_32X32_TARGET_EXP,
+    NIOS_PKT_32X32_TARGET_EXP_DIR, NIOS_PKT_8X16_ADDR_IQ_CORR_TX_PHASE, NIOS_PKT_8X16_MAGIC,
+    NIOS_PKT_8X16_TARGET_IQ_CORR, NIOS_PKT_8X16_TARGET_VCTCXO_DAC, NIOS_PKT_8X32_MAGIC,
+    NIOS_PKT_8X32_TARGET_ADF4351, NIOS_PKT_8X32_TARGET_CONTROL, NIOS_PKT_8X32_TARGET_VERSION,
+    NIOS_PKT_8X64_MAGIC, NIOS_PKT_8X64_TARGET_TIMESTAMP, NIOS_PKT_8X64_TIMESTAMP_RX,
+    NIOS_PKT_8X64_TIMESTAMP_TX, NIOS_PKT_8X8_MAGIC, NIOS_PKT_8X8_TARGET_LMS6,
+    NIOS_PKT_8X8_TARGET_SI5338, NIOS_PKT_FLAG_SUCCESS, NIOS_PKT_FLAG_WRITE, NIOS_PKT_IDX_ADDR,
+    NIOS_PKT_IDX_FLAGS, NIOS_PKT_IDX_MAGIC, NIOS_PKT_IDX_TARGET_ID,
+};
+use crate::nios::constants_legacy::{
+    NIOS_PKT_LEGACY_DEV_CONFIG, NIOS_PKT_LEGACY_DEV_LMS, NIOS_PKT_LEGACY_DEV_SI5338,
+    NIOS_PKT_LEGACY_MAGIC, NIOS_PKT_LEGACY_MODE_DIR_READ, NIOS_PKT_LEGACY_MODE_DIR_WRITE,
+    NIOS_PKT_LEGACY_PIO_ADDR_CONTROL, NIOS_PKT_LEGACY_PIO_ADDR_EXP,
+    NIOS_PKT_LEGACY_PIO_ADDR_EXP_DIR, NIOS_PKT_LEGACY_PIO_ADDR_FPGA_VERSION,
+    NIOS_PKT_LEGACY_PIO_ADDR_IQ_RX_GAIN, NIOS_PKT_LEGACY_PIO_ADDR_RX_TIMESTAMP,
+    NIOS_PKT_LEGACY_PIO_ADDR_TX_TIMESTAMP, NIOS_PKT_LEGACY_PIO_ADDR_VCTCXO,
+    NIOS_PKT_LEGACY_PIO_ADDR_XB200_SYNTH, NIOS_PKT_LEGACY_PIO_LEN_CONTROL,
+    NIOS_PKT_LEGACY_PIO_LEN_EXP, NIOS_PKT_LEGACY_PIO_LEN_EXP_DIR,
+    NIOS_PKT_LEGACY_PIO_LEN_FPGA_VERSION, NIOS_PKT_LEGACY_PIO_LEN_IQ_RX_GAIN,
+    NIOS_PKT_LEGACY_PIO_LEN_RX_TIMESTAMP, NIOS_PKT_LEGACY_PIO_LEN_TX_TIMESTAMP,
+    NIOS_PKT_LEGACY_PIO_LEN_VCTCXO, NIOS_PKT_LEGACY_PIO_LEN_XB200_SYNTH,
+};
+use crate::nios::packet::NIOS_PKT_LEN;
+use crate::nios::packet_legacy::NiosPacketLegacy;
+use anyhow::{anyhow, Result};
+use futures_lite::future::block_on;
+use nusb::transfer::RequestBuffer;
+use nusb::Interface;
+
+/* DAC161S055 registers, as accessed through the 8x16 VCTCXO DAC target */
+const DAC161S055_ADDR_CONFIG: u64 = 0x28;
+const DAC161S055_ADDR_DATA: u64 = 0x08;
+
+/* Number of timestamp bytes read back per request (see packet_legacy.rs, note 3) */
+const LEGACY_TIMESTAMP_BURST: usize = 4;
+
+/* Location of an AxB packet target in the legacy address space */
+struct LegacyTarget {
+    device: u8,
+    addr: u8,
+    len: usize,
+    burst: usize,
+}
+
+impl LegacyTarget {
+    fn new(device: u8, addr: u8, len: u8) -> Self {
+        Self {
+            device,
+            addr,
+            len: len as usize,
+            burst: 1,
+        }
+    }
+}
+
+/* Address and data widths, in bytes, of the AxB packet formats */
+fn axb_format(magic: u8) -> Option<(usize, usize)> {
+    match magic {
+        NIOS_PKT_8X8_MAGIC => Some((1, 1)),
+        NIOS_PKT_8X16_MAGIC => Some((1, 2)),
+        NIOS_PKT_8X32_MAGIC => Some((1, 4)),
+        NIOS_PKT_8X64_MAGIC => Some((1, 8)),
+        NIOS_PKT_16X64_MAGIC => Some((2, 8)),
+        NIOS_PKT_32X32_MAGIC => Some((4, 4)),
+        _ => None,
+    }
+}
+
+/*
+ * Map an AxB target to the legacy device and config PIO address implementing it.
+ * Returns None for accesses that have no legacy equivalent and need no action.
+ */
+fn legacy_target(magic: u8, target_id: u8, addr: u64) -> Result<Option<LegacyTarget>> {
+    let target = match (magic, target_id) {
+        (NIOS_PKT_8X8_MAGIC, NIOS_PKT_8X8_TARGET_LMS6) => {
+            LegacyTarget::new(NIOS_PKT_LEGACY_DEV_LMS, addr as u8, 1)
+        }
+        (NIOS_PKT_8X8_MAGIC, NIOS_PKT_8X8_TARGET_SI5338) => {
+            LegacyTarget::new(NIOS_PKT_LEGACY_DEV_SI5338, addr as u8, 1)
+        }
+        (NIOS_PKT_8X16_MAGIC, NIOS_PKT_8X16_TARGET_VCTCXO_DAC) => match addr {
+            /* The legacy handler always drives the DAC in write-through mode */
+            DAC161S055_ADDR_CONFIG => return Ok(None),
+            DAC161S055_ADDR_DATA => LegacyTarget::new(
+                NIOS_PKT_LEGACY_DEV_CONFIG,
+                NIOS_PKT_LEGACY_PIO_ADDR_VCTCXO,
+                NIOS_PKT_LEGACY_PIO_LEN_VCTCXO,
+            ),
+            _ => return Err(anyhow!("Invalid VCTCXO DAC address: {:#x}", addr)),
+        },
+        (NIOS_PKT_8X16_MAGIC, NIOS_PKT_8X16_TARGET_IQ_CORR)
+            if addr <= NIOS_PKT_8X16_ADDR_IQ_CORR_TX_PHASE as u64 =>
+        {
+            /* RX gain, RX phase, TX gain and TX phase are consecutive 16-bit PIOs */
+            LegacyTarget::new(
+                NIOS_PKT_LEGACY_DEV_CONFIG,
+                NIOS_PKT_LEGACY_PIO_ADDR_IQ_RX_GAIN + 2 * addr as u8,
+                NIOS_PKT_LEGACY_PIO_LEN_IQ_RX_GAIN,
+            )
+        }
+        (NIOS_PKT_8X32_MAGIC, NIOS_PKT_8X32_TARGET_VERSION) => LegacyTarget::new(
+            NIOS_PKT_LEGACY_DEV_CONFIG,
+            NIOS_PKT_LEGACY_PIO_ADDR_FPGA_VERSION,
+            NIOS_PKT_LEGACY_PIO_LEN_FPGA_VERSION,
+        ),
+        (NIOS_PKT_8X32_MAGIC, NIOS_PKT_8X32_TARGET_CONTROL) => LegacyTarget::new(
+            NIOS_PKT_LEGACY_DEV_CONFIG,
+            NIOS_PKT_LEGACY_PIO_ADDR_CONTROL,
+            NIOS_PKT_LEGACY_PIO_LEN_CONTROL,
+        ),
+        (NIOS_PKT_8X32_MAGIC, NIOS_PKT_8X32_TARGET_ADF4351) => LegacyTarget::new(
+            NIOS_PKT_LEGACY_DEV_CONFIG,
+            NIOS_PKT_LEGACY_PIO_ADDR_XB200_SYNTH,
+            NIOS_PKT_LEGACY_PIO_LEN_XB200_SYNTH,
+        ),
+        (NIOS_PKT_8X64_MAGIC, NIOS_PKT_8X64_TARGET_TIMESTAMP) => {
+            let mut target = match addr as u8 {
+                NIOS_PKT_8X64_TIMESTAMP_RX => LegacyTarget::new(
+                    NIOS_PKT_LEGACY_DEV_CONFIG,
+                    NIOS_PKT_LEGACY_PIO_ADDR_RX_TIMESTAMP,
+                    NIOS_PKT_LEGACY_PIO_LEN_RX_TIMESTAMP,
+                ),
+                NIOS_PKT_8X64_TIMESTAMP_TX => LegacyTarget::new(
+                    NIOS_PKT_LEGACY_DEV_CONFIG,
+                    NIOS_PKT_LEGACY_PIO_ADDR_TX_TIMESTAMP,
+                    NIOS_PKT_LEGACY_PIO_LEN_TX_TIMESTAMP,
+                ),
+                _ => return Err(anyhow!("Invalid timestamp address: {:#x}", addr)),
+            };
+            target.burst = LEGACY_TIMESTAMP_BURST;
+            target
+        }
+        (NIOS_PKT_32X32_MAGIC, NIOS_PKT_32X32_TARGET_EXP) => LegacyTarget::new(
+            NIOS_PKT_LEGACY_DEV_CONFIG,
+            NIOS_PKT_LEGACY_PIO_ADDR_EXP,
+            NIOS_PKT_LEGACY_PIO_LEN_EXP,
+        ),
+        (NIOS_PKT_32X32_MAGIC, NIOS_PKT_32X32_TARGET_EXP_DIR) => LegacyTarget::new(
+            NIOS_PKT_LEGACY_DEV_CONFIG,
+            NIOS_PKT_LEGACY_PIO_ADDR_EXP_DIR,
+            NIOS_PKT_LEGACY_PIO_LEN_EXP_DIR,
+        ),
+        _ => {
+            return Err(anyhow!(
+                "Target {:#x} of packet format '{}' is not supported by the legacy packet handler",
+                target_id,
+                magic as char
+            ))
+        }
+    };
+
+    Ok(Some(target))
+}
+
+/* All bits of a target of `len` bytes */
+fn full_mask(len: usize) -> u64 {
+    u64::MAX >> (64 - 8 * len)
+}
+
+/* Bits of a target of `len` bytes covered by a request. 32x32 targets use the
+ * address field as a bit mask. */
+fn access_mask(magic: u8, addr: u64, len: usize) -> u64 {
+    if magic == NIOS_PKT_32X32_MAGIC {
+        addr & full_mask(len)
+    } else {
+        full_mask(len)
+    }
+}
+
+/* Replace the bits of `current` selected by `mask` with those of `data` */
+fn masked_update(current: u64, data: u64, mask: u64) -> u64 {
+    (current & !mask) | (data & mask)
+}
+
+/* The legacy PIO holds major, minor, patch (LSB first); the 8x32 version
+ * target reports major << 24 | minor << 16 | patch */
+fn version_from_legacy(value: u64) -> u64 {
+    ((value & 0xff) << 24) | (((value >> 8) & 0xff) << 16) | ((value >> 16) & 0xffff)
+}
+
+/// Perform a single legacy packet transaction with up to `NIOS_PKT_LEGACY_MAX_PAIRS`
+/// addr/data pairs. The returned response holds the data read on a read request.
+pub fn legacy_access(
+    interface: &Interface,
+    endpoint_in: u8,
+    endpoint_out: u8,
+    device: u8,
+    dir: u8,
+    pairs: &[(u8, u8)],
+) -> Result<NiosPacketLegacy> {
+    let mut request = NiosPacketLegacy::new();
+    request.set(device, dir, pairs)?;
+
+    let pkt = request.into_vec();
+    log::trace!("BulkOut: {:x?}", pkt);
+    let response = block_on(interface.bulk_out(endpoint_out, pkt)).into_result()?;
+
+    let response =
+        block_on(interface.bulk_in(endpoint_in, RequestBuffer::reuse(response.reuse(), 16)))
+            .into_result()?;
+    log::trace!("BulkIn:  {:x?}", response);
+
+    let response = NiosPacketLegacy::from_vec(response)?;
+    if response.magic() != NIOS_PKT_LEGACY_MAGIC {
+        return Err(anyhow!(
+            "Invalid legacy response magic: {:#x}",
+            response.magic()
+        ));
+    }
+    Ok(response)
+}
+
+/* Read a little-endian value of `len` bytes from consecutive legacy addresses */
+fn legacy_read(
+    interface: &Interface,
+    endpoint_in: u8,
+    endpoint_out: u8,
+    target: &LegacyTarget,
+) -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    for offset in (0..target.len).step_by(target.burst) {
+        let count = target.burst.min(target.len - offset);
+        let pairs: Vec<(u8, u8)> = (offset..offset + count)
+            .map(|i| (target.addr + i as u8, 0xff))
+            .collect();
+
+        let response = legacy_access(
+            interface,
+            endpoint_in,
+            endpoint_out,
+            target.device,
+            NIOS_PKT_LEGACY_MODE_DIR_READ,
+            &pairs,
+        )?;
+        for i in 0..count {
+            bytes[offset + i] = response.pair(i).1;
+        }
+    }
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/* Write a little-endian value of `len` bytes to consecutive legacy addresses */
+fn legacy_write(
+    interface: &Interface,
+    endpoint_in: u8,
+    endpoint_out: u8,
+    target: &LegacyTarget,
+    value: u64,
+) -> Result<()> {
+    let bytes = value.to_le_bytes();
+    for (i, &byte) in bytes.iter().enumerate().take(target.len) {
+        legacy_access(
+            interface,
+            endpoint_in,
+            endpoint_out,
+            target.device,
+            NIOS_PKT_LEGACY_MODE_DIR_WRITE,
+            &[(target.addr + i as u8, byte)],
+        )?;
+    }
+    Ok(())
+}
+
+/// Carry out an AxB request through the legacy packet handler, and synthesize the AxB
+/// response the newer packet handlers would have sent.
+pub fn legacy_send(
+    interface: &Interface,
+    endpoint_in: u8,
+    endpoint_out: u8,
+    pkt: Vec<u8>,
+) -> Result<Vec<u8>> {
+    if pkt.len() < NIOS_PKT_LEN {
+        return Err(anyhow!(
+            "NIOS packet too short: {} bytes, expected {}",
+            pkt.len(),
+            NIOS_PKT_LEN
+        ));
+    }
+
+    let magic = pkt[NIOS_PKT_IDX_MAGIC];
+    let (addr_len, data_len) =
+        axb_format(magic).ok_or_else(|| anyhow!("Invalid NIOS packet magic: {:#x}", magic))?;
+    let target_id = pkt[NIOS_PKT_IDX_TARGET_ID];
+    let write = (pkt[NIOS_PKT_IDX_FLAGS] & NIOS_PKT_FLAG_WRITE) != 0;
+
+    let data_idx = NIOS_PKT_IDX_ADDR + addr_len;
+    let le_value = |bytes: &[u8]| {
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(buf)
+    };
+    let addr = le_value(&pkt[NIOS_PKT_IDX_ADDR..data_idx]);
+    let data = le_value(&pkt[data_idx..data_idx + data_len]);
+
+    let mut response = pkt;
+    response[NIOS_PKT_IDX_FLAGS] |= NIOS_PKT_FLAG_SUCCESS;
+
+    let Some(target) = legacy_target(magic, target_id, addr)? else {
+        return Ok(response);
+    };
+
+    let mask = access_mask(magic, addr, target.len);
+
+    let value = if write {
+        let value = if mask != full_mask(target.len) {
+            let current = legacy_read(interface, endpoint_in, endpoint_out, &target)?;
+            masked_update(current, data, mask)
+        } else {
+            data
+        };
+        legacy_write(interface, endpoint_in, endpoint_out, &target, value)?;
+        data
+    } else {
+        let value = legacy_read(interface, endpoint_in, endpoint_out, &target)?;
+        if magic == NIOS_PKT_8X32_MAGIC && target_id == NIOS_PKT_8X32_TARGET_VERSION {
+            version_from_legacy(value)
+        } else {
+            value & mask
+        }
+    };
+
+    response[data_idx..data_idx + data_len].copy_from_slice(&value.to_le_bytes()[..data_len]);
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nios::constants::{
+        NIOS_PKT_8X16_ADDR_IQ_CORR_RX_GAIN, NIOS_PKT_8X16_ADDR_IQ_CORR_RX_PHASE,
+        NIOS_PKT_8X16_ADDR_IQ_CORR_TX_GAIN,
+    };
+    use crate::nios::constants_legacy::{
+        NIOS_PKT_LEGACY_PIO_ADDR_IQ_RX_PHASE, NIOS_PKT_LEGACY_PIO_ADDR_IQ_TX_GAIN,
+        NIOS_PKT_LEGACY_PIO_ADDR_IQ_TX_PHASE,
+    };
+
+    #[test]
+    fn iq_corr_addresses() {
+        let expected = [
+            (
+                NIOS_PKT_8X16_ADDR_IQ_CORR_RX_GAIN,
+                NIOS_PKT_LEGACY_PIO_ADDR_IQ_RX_GAIN,
+            ),
+            (
+                NIOS_PKT_8X16_ADDR_IQ_CORR_RX_PHASE,
+                NIOS_PKT_LEGACY_PIO_ADDR_IQ_RX_PHASE,
+            ),
+            (
+                NIOS_PKT_8X16_ADDR_IQ_CORR_TX_GAIN,
+                NIOS_PKT_LEGACY_PIO_ADDR_IQ_TX_GAIN,
+            ),
+            (
+                NIOS_PKT_8X16_ADDR_IQ_CORR_TX_PHASE,
+                NIOS_PKT_LEGACY_PIO_ADDR_IQ_TX_PHASE,
+            ),
+        ];
+
+        for (addr, pio_addr) in expected {
+            let target = legacy_target(
+                NIOS_PKT_8X16_MAGIC,
+                NIOS_PKT_8X16_TARGET_IQ_CORR,
+                addr as u64,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(target.device, NIOS_PKT_LEGACY_DEV_CONFIG);
+            assert_eq!(target.addr, pio_addr);
+            assert_eq!(target.len, 2);
+        }
+
+        let past_end = NIOS_PKT_8X16_ADDR_IQ_CORR_TX_PHASE as u64 + 1;
+        assert!(
+            legacy_target(NIOS_PKT_8X16_MAGIC, NIOS_PKT_8X16_TARGET_IQ_CORR, past_end).is_err()
+        );
+    }
+
+    #[test]
+    fn targets_without_legacy_equivalent() {
+        /* DAC configuration writes are skipped, everything unknown is rejected */
+        assert!(legacy_target(
+            NIOS_PKT_8X16_MAGIC,
+            NIOS_PKT_8X16_TARGET_VCTCXO_DAC,
+            DAC161S055_ADDR_CONFIG
+        )
+        .unwrap()
+        .is_none());
+        assert!(legacy_target(NIOS_PKT_8X16_MAGIC, NIOS_PKT_8X16_TARGET_VCTCXO_DAC, 0x01).is_err());
+        assert!(legacy_target(NIOS_PKT_8X64_MAGIC, NIOS_PKT_8X64_TARGET_TIMESTAMP, 0x02).is_err());
+        assert!(legacy_target(NIOS_PKT_16X64_MAGIC, 0x00, 0x00).is_err());
+    }
+
+    #[test]
+    fn masked_32x32_write() {
+        let target = legacy_target(NIOS_PKT_32X32_MAGIC, NIOS_PKT_32X32_TARGET_EXP, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.len, 4);
+
+        /* The address field selects the bits to modify, limited to the target width */
+        let mask = access_mask(NIOS_PKT_32X32_MAGIC, 0x1_0000_ff00, target.len);
+        assert_eq!(mask, 0x0000_ff00);
+        assert_eq!(masked_update(0x1234_5678, 0xaaaa_aaaa, mask), 0x1234_aa78);
+
+        /* Other formats always access the whole target */
+        assert_eq!(access_mask(NIOS_PKT_8X32_MAGIC, 0x12, 4), 0xffff_ffff);
+        assert_eq!(access_mask(NIOS_PKT_8X8_MAGIC, 0x12, 1), 0xff);
+        assert_eq!(access_mask(NIOS_PKT_8X64_MAGIC, 0x00, 8), u64::MAX);
+    }
+
+    #[test]
+    fn fpga_version_conversion() {
+        /* Version 0.7.3 as read from the legacy PIO bytes, LSB first */
+        let value = u32::from_le_bytes([0x00, 0x07, 0x03, 0x00]) as u64;
+        assert_eq!(version_from_legacy(value), 0x0007_0003);
+
+        let value = u32::from_le_bytes([0x01, 0x02, 0x34, 0x12]) as u64;
+        assert_eq!(version_from_legacy(value), 0x0102_1234);
+    }
+}
@@ -1,626 +1,415 @@
-// #![allow(unsafe_code)]
-//
-// use super::constants::*;
-// //use std::fmt::Debug;
-// use std::mem::ManuallyDrop;
-// //use std::ops::Add;
-//
-// enum Direction {
-//     RX = 0x0,
-//     TX = 0x1,
-// }
-//
-// const fn get_module(direction: Direction, channel: u8) -> u8 {
-//     if direction as u8 == Direction::RX as u8 {
-//         (channel << 1) | 0x0
-//     } else {
-//         (channel << 1) | 0x1
-//     }
-// }
-//
-// pub const BLADERF_MODULE_RX: u8 = get_module(Direction::RX, 0);
-// pub const BLADERF_MODULE_TX: u8 = get_module(Direction::TX, 0);
-//
-// /*
-//  * This file defines the Host <-> FPGA (NIOS II) packet formats for accesses
-//  * to devices/blocks with 8-bit addresses and 8-bit data
-//  *
-//  *
-//  *                              Request
-//  *                      ----------------------
-//  *
-//  * +================+=========================================================+
-//  * |  Byte offset   |                       Description                       |
-//  * +================+=========================================================+
-//  * |        0       | Magic Value                                             |
-//  * +----------------+---------------------------------------------------------+
-//  * |        1       | Target ID (Note 1)                                      |
-//  * +----------------+---------------------------------------------------------+
-//  * |        2       | Flags (Note 2)                                          |
-//  * +----------------+---------------------------------------------------------+
-//  * |        3       | Reserved. Set to 0x00.                                  |
-//  * +----------------+---------------------------------------------------------+
-//  * |        4       | 8-bit address                                           |
-//  * +----------------+---------------------------------------------------------+
-//  * |        5       | 8-bit data                                              |
-//  * +----------------+---------------------------------------------------------+
-//  * |      15:6      | Reserved. Set to 0.                                     |
-//  * +----------------+---------------------------------------------------------+
-//  *
-//  *
-//  *                              Response
-//  *                      ----------------------
-//  *
-//  * The response packet contains the same information as the request.
-//  * A status flag will be set if the operation completed successfully.
-//  *
-//  * In the case of a read request, the data field will contain the read data, if
-//  * the read succeeded.
-//  *
-//  * (Note 1)
-//  *  The "Target ID" refers to the peripheral, device, or block to access.
-//  *  See the NIOS_PKT_8x8_TARGET_* values.
-//  *
-//  * (Note 2)
-//  *  The flags are defined as follows:
-//  *
-//  *    +================+========================+
-//  *    |      Bit(s)    |         Value          |
-//  *    +================+========================+
-//  *    |       7:2      | Reserved. Set to 0.    |
-//  *    +----------------+------------------------+
-//  *    |                | Status. Only used in   |
-//  *    |                | response packet.       |
-//  *    |                | Ignored in request.    |
-//  *    |        1       |                        |
-//  *    |                |   1 = Success          |
-//  *    |                |   0 = Failure          |
-//  *    +----------------+------------------------+
-//  *    |        0       |   0 = Read operation   |
-//  *    |                |   1 = Write operation  |
-//  *    +----------------+------------------------+
-//  *
-//  */
-//
-// pub struct Packet {
-//     buf: *mut u8,
-// }
-// impl Packet {
-//     pub fn as_mut_ptr(&mut self) -> *mut u8 {
-//         self.buf
-//     }
-//
-//     pub fn from_vec(v: Vec<u8>) -> Self {
-//         let mut v = ManuallyDrop::new(v);
-//         Self {
-//             buf: v.as_mut_ptr(),
-//         }
-//     }
-//
-//     pub fn new() -> Self {
-//         Self::from_vec(Vec::<u8>::from([0u8; 16]))
-//     }
-//
-//     pub fn into_vec(self) -> Vec<u8> {
-//         let mut s = ManuallyDrop::new(self);
-//         unsafe { Vec::<u8>::from_raw_parts(s.as_mut_ptr(), 16, 16) }
-//     }
-//
-//     pub fn reuse(v: Vec<u8>) -> Self {
-//         let mut v = ManuallyDrop::new(v);
-//         v.clear();
-//         v.reserve_exact(16);
-//         Self {
-//             buf: v.as_mut_ptr(),
-//         }
-//     }
-// }
-//
-// impl Drop for Packet {
-//     fn drop(&mut self) {
-//         unsafe { drop(Vec::from_raw_parts(self.buf, 16, 16)) }
-//     }
-// }
-//
-// pub struct NiosPacket {
-//     pub pkt: Packet,
-// }
-//
-// impl NiosPacket {
-//     pub fn new() -> Self {
-//         Self { pkt: Packet::new() }
-//     }
-//
-//     pub fn magic(&self) -> u8 {
-//         unsafe { self.pkt.buf.read() }
-//     }
-//     pub fn target_id(&self) -> u8 {
-//         unsafe { self.pkt.buf.add(NIOS_PKT_IDX_TARGET_ID).read() }
-//     }
-//
-//     pub fn flags(&self) -> u8 {
-//         unsafe { self.pkt.buf.add(NIOS_PKT_IDX_FLAGS).read() }
-//     }
-//
-//     pub fn is_write(&self) -> bool {
-//         unsafe { (self.pkt.buf.add(NIOS_PKT_IDX_FLAGS).read() & NIOS_PKT_FLAG_WRITE) != 0 }
-//     }
-//
-//     pub fn set_magic(&mut self, magic: u8) -> &mut Self {
-//         unsafe {
-//             self.pkt.buf.add(NIOS_PKT_IDX_MAGIC).write(magic);
-//         }
-//         self
-//     }
-//     pub fn set_target_id(&mut self, target_id: u8) -> &mut Self {
-//         unsafe {
-//             self.pkt.buf.add(NIOS_PKT_IDX_TARGET_ID).write(target_id);
-//         }
-//         self
-//     }
-//     pub fn set_flag(&mut self, flag: u8) -> &mut Self {
-//         unsafe {
-//             let flags = self.pkt.buf.add(NIOS_PKT_IDX_FLAGS).read();
-//             self.pkt.buf.add(NIOS_PKT_IDX_FLAGS).write(flags | flag);
-//         }
-//         self
-//     }
-//     pub fn set_flags(&mut self, flags: u8) -> &mut Self {
-//         unsafe {
-//             self.pkt.buf.add(NIOS_PKT_IDX_FLAGS).write(flags);
-//         }
-//         self
-//     }
-// }
-//
-// pub struct NiosPacket8x8 {
-//     pub pkt: NiosPacket,
-// }
-//
-// impl NiosPacket8x8 {
-//     pub fn new() -> Self {
-//         let mut pkt = NiosPacket::new();
-//         pkt.set_magic(NIOS_PKT_8X8_MAGIC);
-//         Self { pkt }
-//     }
-//
-//     pub fn set_addr(&mut self, addr: u8) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u8>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR)
-//                 .copy_from(addr.to_le_bytes().as_ptr(), ADDR_SIZE)
-//         }
-//         self
-//     }
-//     pub fn set_data(&mut self, data: u8) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u8>();
-//             const DATA_SIZE: usize = size_of::<u8>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR + ADDR_SIZE)
-//                 .copy_from(data.to_le_bytes().as_ptr(), DATA_SIZE)
-//         }
-//         self
-//     }
-// }
-//
-// pub struct NiosPacket8x16 {
-//     pub pkt: NiosPacket,
-// }
-//
-// impl NiosPacket8x16 {
-//     pub fn new() -> Self {
-//         let mut pkt = NiosPacket::new();
-//         pkt.set_magic(NIOS_PKT_8X16_MAGIC);
-//         Self { pkt }
-//     }
-//
-//     pub fn set_addr(&mut self, addr: u8) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u8>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR)
-//                 .copy_from(addr.to_le_bytes().as_ptr(), ADDR_SIZE)
-//         }
-//         self
-//     }
-//     pub fn set_data(&mut self, data: u16) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u8>();
-//             const DATA_SIZE: usize = size_of::<u16>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR + ADDR_SIZE)
-//                 .copy_from(data.to_le_bytes().as_ptr(), DATA_SIZE)
-//         }
-//         self
-//     }
-// }
-//
-// pub struct NiosPacket8x32 {
-//     pub pkt: NiosPacket,
-// }
-//
-// impl NiosPacket8x32 {
-//     pub fn new() -> Self {
-//         let mut pkt = NiosPacket::new();
-//         pkt.set_magic(NIOS_PKT_8X32_MAGIC);
-//         Self { pkt }
-//     }
-//
-//     pub fn set_addr(&mut self, addr: u8) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u8>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR)
-//                 .copy_from(addr.to_le_bytes().as_ptr(), ADDR_SIZE)
-//         }
-//         self
-//     }
-//     pub fn set_data(&mut self, data: u32) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u8>();
-//             const DATA_SIZE: usize = size_of::<u32>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR + ADDR_SIZE)
-//                 .copy_from(data.to_le_bytes().as_ptr(), DATA_SIZE)
-//         }
-//         self
-//     }
-// }
-//
-// pub struct NiosPacket8x64 {
-//     pub pkt: NiosPacket,
-// }
-//
-// impl NiosPacket8x64 {
-//     pub fn new() -> Self {
-//         let mut pkt = NiosPacket::new();
-//         pkt.set_magic(NIOS_PKT_8X64_MAGIC);
-//         Self { pkt }
-//     }
-//
-//     pub fn set_addr(&mut self, addr: u8) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u8>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR)
-//                 .copy_from(addr.to_le_bytes().as_ptr(), ADDR_SIZE)
-//         }
-//         self
-//     }
-//     pub fn set_data(&mut self, data: u64) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u8>();
-//             const DATA_SIZE: usize = size_of::<u64>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR + ADDR_SIZE)
-//                 .copy_from(data.to_le_bytes().as_ptr(), DATA_SIZE)
-//         }
-//         self
-//     }
-// }
-//
-// pub struct NiosPacket32x32 {
-//     pub pkt: NiosPacket,
-// }
-//
-// impl NiosPacket32x32 {
-//     pub fn new() -> Self {
-//         let mut pkt = NiosPacket::new();
-//         pkt.set_magic(NIOS_PKT_32X32_MAGIC);
-//         Self { pkt }
-//     }
-//
-//     pub fn set_addr(&mut self, addr: u32) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u32>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR)
-//                 .copy_from(addr.to_le_bytes().as_ptr(), ADDR_SIZE)
-//         }
-//         self
-//     }
-//     pub fn set_data(&mut self, data: u32) -> &mut Self {
-//         unsafe {
-//             const ADDR_SIZE: usize = size_of::<u32>();
-//             const DATA_SIZE: usize = size_of::<u32>();
-//
-//             self.pkt
-//                 .pkt
-//                 .buf
-//                 .add(NIOS_PKT_IDX_ADDR + ADDR_SIZE)
-//                 .copy_from(data.to_le_bytes().as_ptr(), DATA_SIZE)
-//         }
-//         self
-//     }
-// }
-//
-// // https://stackoverflow.com/questions/78395612/how-to-enforce-generic-parameter-to-be-of-type-u8-u16-u32-or-u64-in-rust
-// // https://predr.ag/blog/definitive-guide-to-sealed-traits-in-rust/
-// // trait Marker {}
-// //
-// // impl Marker for u8 {}
-// // impl Marker for u16 {}
-// // impl Marker for u32 {}
-// // impl Marker for u64 {}
-// //
-// // pub struct NiosPkt<A, D>
-// // where
-// //     A: Marker,
-// //     D: Marker,
-// // {
-// //     buf: *mut u8,
-// //     phantom: std::marker::PhantomData<(A, D)>,
-// // }
-// //
-// // impl<A, D> NiosPkt<A, D>
-// // where
-// //     A: Marker + Sized,
-// //     D: Marker + Sized,
-// // {
-// //     pub fn new(target_id: u8, flags: u8, addr: A, data: D) -> Self {
-// //         let mut pkt = Self::from_vec(Vec::<u8>::from([0u8; 16]));
-// //         pkt.set(target_id, flags, addr, data);
-// //         pkt
-// //     }
-// //
-// //     pub fn set(&mut self, target_id: u8, flags: u8, addr: A, data: D) {
-// //         let magic = match (size_of::<A>(), size_of::<D>()) {
-// //             (1, 1) => NIOS_PKT_8X8_MAGIC,
-// //             (1, 2) => NIOS_PKT_8X16_MAGIC,
-// //             (1, 4) => NIOS_PKT_8X32_MAGIC,
-// //             (1, 8) => NIOS_PKT_8X64_MAGIC,
-// //             (2, 8) => NIOS_PKT_16X64_MAGIC,
-// //             (4, 4) => NIOS_PKT_32X32_MAGIC,
-// //             _ => panic!("Wrong type sizes for NIOS packet"),
-// //         };
-// //         self.set_magic(magic);
-// //         self.set_target_id(target_id);
-// //         self.set_flags(flags);
-// //         self.set_addr(addr);
-// //         self.set_data(data);
-// //     }
-// //
-// //     pub fn from_vec(v: Vec<u8>) -> Self {
-// //         let mut v = ManuallyDrop::new(v);
-// //         Self {
-// //             buf: v.as_mut_ptr(),
-// //             phantom: Default::default(),
-// //         }
-// //     }
-// //
-// //     pub fn into_vec(self) -> Vec<u8> {
-// //         let s = ManuallyDrop::new(self);
-// //         unsafe { Vec::<u8>::from_raw_parts(s.buf, 16, 16) }
-// //     }
-// //
-// //     pub fn reuse(v: Vec<u8>) -> Self {
-// //         let mut v = ManuallyDrop::new(v);
-// //         v.clear();
-// //         v.reserve_exact(16);
-// //         Self {
-// //             buf: v.as_mut_ptr(),
-// //             phantom: Default::default(),
-// //         }
-// //     }
-// //
-// //     pub fn magic(&self) -> u8 {
-// //         unsafe { self.buf.read() }
-// //     }
-// //     pub fn target_id(&self) -> u8 {
-// //         unsafe { self.buf.add(NIOS_PKT_IDX_TARGET_ID).read() }
-// //     }
-// //
-// //     pub fn flags(&self) -> u8 {
-// //         unsafe { self.buf.add(NIOS_PKT_IDX_FLAGS).read() }
-// //     }
-// //
-// //     pub fn is_write(&self) -> bool {
-// //         unsafe { (self.buf.add(NIOS_PKT_IDX_FLAGS).read() & NIOS_PKT_FLAG_WRITE) != 0 }
-// //     }
-// //
-// //     pub fn set_magic(&mut self, magic: u8) -> &mut Self {
-// //         unsafe {
-// //             self.buf.add(NIOS_PKT_IDX_MAGIC).write(magic);
-// //         }
-// //         self
-// //     }
-// //     pub fn set_target_id(&mut self, target_id: u8) -> &mut Self {
-// //         unsafe {
-// //             self.buf.add(NIOS_PKT_IDX_TARGET_ID).write(target_id);
-// //         }
-// //         self
-// //     }
-// //     pub fn set_flag(&mut self, flag: u8) -> &mut Self {
-// //         unsafe {
-// //             let flags = self.buf.add(NIOS_PKT_IDX_FLAGS).read();
-// //             self.buf.add(NIOS_PKT_IDX_FLAGS).write(flags | flag);
-// //         }
-// //         self
-// //     }
-// //     pub fn set_flags(&mut self, flags: u8) -> &mut Self {
-// //         unsafe {
-// //             self.buf.add(NIOS_PKT_IDX_FLAGS).write(flags);
-// //         }
-// //         self
-// //     }
-// //
-// //     pub fn set_addr(&mut self, addr: A) -> &mut Self {
-// //         unsafe {
-// //             let addr_bytes = std::mem::transmute::<*const A, [u8; size_of::<*const A>()]>(addr);
-// //             self.buf
-// //                 .add(NIOS_PKT_IDX_ADDR)
-// //                 .copy_from(addr_bytes.as_ptr(), size_of::<A>())
-// //         }
-// //         self
-// //     }
-// //     pub fn set_data(&mut self, data: D) -> &mut Self {
-// //         unsafe {
-// //             let size = size_of::<D>();
-// //             let data_bytes = std::mem::transmute::<D, [u8; size]>(data);
-// //             self.buf
-// //                 .add(NIOS_PKT_IDX_ADDR + size_of::<A>())
-// //                 .copy_from(data_bytes.as_ptr(), size_of::<D>())
-// //         }
-// //         self
-// //     }
-// // }
-// //
-// // pub trait Packet {
-// //
-// // }
-// // pub trait Nios : Packet {
-// //     type AddressType; // Placeholder for concrete type
-// //     type DataType; // Placeholder for concrete type
-// //     fn addr(&self) -> Self::AddressType;
-// //     fn data(&self) -> Self::DataType;
-// // }
-// //
-// // impl Nios for NiosPkt<u8, u8> {
-// //     type AddressType = u8;
-// //     type DataType = u8;
-// //
-// //     fn addr(&self) -> Self::AddressType {
-// //         let mut bytes = [0u8; size_of::<Self::AddressType>()];
-// //         unsafe {
-// //             self.buf
-// //                 .add(NIOS_PKT_IDX_ADDR)
-// //                 .copy_to(bytes.as_mut_ptr(), size_of::<Self::AddressType>())
-// //         }
-// //         Self::AddressType::from_le_bytes(bytes)
-// //     }
-// //     fn data(&self) -> Self::DataType {
-// //         let mut bytes = [0u8; size_of::<Self::DataType>()];
-// //         unsafe {
-// //             self.buf
-// //                 .add(NIOS_PKT_IDX_ADDR + size_of::<Self::AddressType>())
-// //                 .copy_to(bytes.as_mut_ptr(), size_of::<Self::DataType>())
-// //         }
-// //         Self::DataType::from_le_bytes(bytes)
-// //     }
-// // }
-// //
-// // impl Nios for NiosPkt<u8, u16> {
-// //     type AddressType = u8;
-// //     type DataType = u16;
-// //
-// //     fn addr(&self) -> Self::AddressType {
-// //         let mut bytes = [0u8; size_of::<Self::AddressType>()];
-// //         unsafe {
-// //             self.buf
-// //                 .add(NIOS_PKT_IDX_ADDR)
-// //                 .copy_to(bytes.as_mut_ptr(), size_of::<Self::AddressType>())
-// //         }
-// //         Self::AddressType::from_le_bytes(bytes)
-// //     }
-// //     fn data(&self) -> Self::DataType {
-// //         let mut bytes = [0u8; size_of::<Self::DataType>()];
-// //         unsafe {
-// //             self.buf
-// //                 .add(NIOS_PKT_IDX_ADDR + size_of::<Self::AddressType>())
-// //                 .copy_to(bytes.as_mut_ptr(), size_of::<Self::DataType>())
-// //         }
-// //         Self::DataType::from_le_bytes(bytes)
-// //     }
-// // }
-// //
-// // impl Nios for NiosPkt<u8, u32> {
-// //     type AddressType = u8;
-// //     type DataType = u32;
-// //
-// //     fn addr(&self) -> Self::AddressType {
-// //         let mut bytes = [0u8; size_of::<Self::AddressType>()];
-// //         unsafe {
-// //             self.buf
-// //                 .add(NIOS_PKT_IDX_ADDR)
-// //                 .copy_to(bytes.as_mut_ptr(), size_of::<Self::AddressType>())
-// //         }
-// //         Self::AddressType::from_le_bytes(bytes)
-// //     }
-// //     fn data(&self) -> Self::DataType {
-// //         let mut bytes = [0u8; size_of::<Self::DataType>()];
-// //         unsafe {
-// //             self.buf
-// //                 .add(NIOS_PKT_IDX_ADDR + size_of::<Self::AddressType>())
-// //                 .copy_to(bytes.as_mut_ptr(), size_of::<Self::DataType>())
-// //         }
-// //         Self::DataType::from_le_bytes(bytes)
-// //     }
-// // }
-// //
-// // impl<A, D> Debug for NiosPkt<A, D>
-// // where
-// //     A: Marker + Sized,
-// //     D: Marker + Sized,
-// // {
-// //     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-// //         let mut s = String::from("NIOS II ");
-// //         if self.is_write() {
-// //             s = s.add("WRITE ")
-// //         } else {
-// //             s = s.add("READ ")
-// //         }
-// //
-// //         f.debug_struct(s.as_str())
-// //             .field("magic", &self.magic())
-// //             .field("target", &self.target_id())
-// //             .field("flags", &self.flags())
-// //             //.field("write", &self.write())
-// //             //.field("success", &self.success())
-// //             //.field("addr", &self.addr())
-// //             //.field("data", &self.data())
-// //             .finish()
-// //         //.finish_non_exhaustive()
-// //     }
-// // }
-// //
-// // unsafe impl<A, D> Send for NiosPkt<A, D>
-// // where
-// //     A: Marker + Sized,
-// //     D: Marker + Sized,
-// // {
-// // }
-// // unsafe impl<A, D> Sync for NiosPkt<A, D>
-// // where
-// //     A: Marker + Sized,
-// //     D: Marker + Sized,
-// // {
-// // }
-// //
-// // impl<A, D> Drop for NiosPkt<A, D>
-// // where
-// //     A: Marker,
-// //     D: Marker,
-// // {
-// //     fn drop(&mut self) {
-// //         unsafe { drop(Vec::from_raw_parts(self.buf, 16, 16)) }
-// //     }
-// // }
+use crate::nios::constants::{
+    NIOS_PKT_16X64_MAGIC, NIOS_PKT_32X32_MAGIC, NIOS_PKT_8X16_MAGIC, NIOS_PKT_8X32_MAGIC,
+    NIOS_PKT_8X64_MAGIC, NIOS_PKT_8X8_MAGIC, NIOS_PKT_FLAG_SUCCESS, NIOS_PKT_FLAG_WRITE,
+    NIOS_PKT_IDX_ADDR, NIOS_PKT_IDX_FLAGS, NIOS_PKT_IDX_MAGIC, NIOS_PKT_IDX_TARGET_ID,
+};
+use anyhow::{anyhow, Result};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/*
+ * This file defines the Host <-> FPGA (NIOS II) packet formats for accesses
+ * to devices/blocks with A-bit addresses and D-bit data, where (A, D) is one
+ * of 8x8, 8x16, 8x32, 8x64, 16x64 or 32x32. Each format is identified by its
+ * magic value (see the NIOS_PKT_AxB_MAGIC constants).
+ *
+ *
+ *                              Request
+ *                      ----------------------
+ *
+ * +================+=========================================================+
+ * |  Byte offset   |                       Description                       |
+ * +================+=========================================================+
+ * |        0       | Magic Value                                             |
+ * +----------------+---------------------------------------------------------+
+ * |        1       | Target ID (Note 1)                                      |
+ * +----------------+---------------------------------------------------------+
+ * |        2       | Flags (Note 2)                                          |
+ * +----------------+---------------------------------------------------------+
+ * |        3       | Reserved. Set to 0x00.                                  |
+ * +----------------+---------------------------------------------------------+
+ * |  4 : 4+A/8-1   | A-bit address, little-endian                            |
+ * +----------------+---------------------------------------------------------+
+ * | 4+A/8 : +D/8-1 | D-bit data, little-endian                               |
+ * +----------------+---------------------------------------------------------+
+ * |   remainder    | Reserved. Set to 0.                                     |
+ * +----------------+---------------------------------------------------------+
+ *
+ *
+ *                              Response
+ *                      ----------------------
+ *
+ * The response packet contains the same information as the request.
+ * A status flag will be set if the operation completed successfully.
+ *
+ * In the case of a read request, the data field will contain the read data, if
+ * the read succeeded.
+ *
+ * (Note 1)
+ *  The "Target ID" refers to the peripheral, device, or block to access.
+ *  See the NIOS_PKT_AxB_TARGET_* values.
+ *
+ * (Note 2)
+ *  The flags are defined as follows:
+ *
+ *    +================+========================+
+ *    |      Bit(s)    |         Value          |
+ *    +================+========================+
+ *    |       7:2      | Reserved. Set to 0.    |
+ *    +----------------+------------------------+
+ *    |                | Status. Only used in   |
+ *    |                | response packet.       |
+ *    |                | Ignored in request.    |
+ *    |        1       |                        |
+ *    |                |   1 = Success          |
+ *    |                |   0 = Failure          |
+ *    +----------------+------------------------+
+ *    |        0       |   0 = Read operation   |
+ *    |                |   1 = Write operation  |
+ *    +----------------+------------------------+
+ *
+ */
+
+pub const NIOS_PKT_LEN: usize = 16;
+
+/// A value that can be stored in the address or data field of a packet.
+pub trait NiosEncode: Copy {
+    /// Width of the field in bytes.
+    const SIZE: usize;
+
+    /// Write the value, little-endian, to the first `SIZE` bytes of `buf`.
+    fn encode(self, buf: &mut [u8]);
+}
+
+/// A value that can be read from the address or data field of a packet.
+pub trait NiosDecode: Sized {
+    /// Read the value, little-endian, from the first bytes of `buf`.
+    fn decode(buf: &[u8]) -> Self;
+}
+
+macro_rules! impl_nios_value {
+    ($($ty:ty),*) => {
+        $(
+            impl NiosEncode for $ty {
+                const SIZE: usize = size_of::<$ty>();
+
+                fn encode(self, buf: &mut [u8]) {
+                    buf[..Self::SIZE].copy_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl NiosDecode for $ty {
+                fn decode(buf: &[u8]) -> Self {
+                    let mut bytes = [0u8; size_of::<$ty>()];
+                    bytes.copy_from_slice(&buf[..size_of::<$ty>()]);
+                    <$ty>::from_le_bytes(bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_nios_value!(u8, u16, u32, u64);
+
+/// An (address, data) type pair with a packet handler in the FPGA.
+pub trait NiosPacketFormat {
+    const MAGIC: u8;
+}
+
+impl NiosPacketFormat for (u8, u8) {
+    const MAGIC: u8 = NIOS_PKT_8X8_MAGIC;
+}
+impl NiosPacketFormat for (u8, u16) {
+    const MAGIC: u8 = NIOS_PKT_8X16_MAGIC;
+}
+impl NiosPacketFormat for (u8, u32) {
+    const MAGIC: u8 = NIOS_PKT_8X32_MAGIC;
+}
+impl NiosPacketFormat for (u8, u64) {
+    const MAGIC: u8 = NIOS_PKT_8X64_MAGIC;
+}
+impl NiosPacketFormat for (u16, u64) {
+    const MAGIC: u8 = NIOS_PKT_16X64_MAGIC;
+}
+impl NiosPacketFormat for (u32, u32) {
+    const MAGIC: u8 = NIOS_PKT_32X32_MAGIC;
+}
+
+pub type NiosPacket8x8 = NiosPacket<u8, u8>;
+pub type NiosPacket8x16 = NiosPacket<u8, u16>;
+pub type NiosPacket8x32 = NiosPacket<u8, u32>;
+pub type NiosPacket8x64 = NiosPacket<u8, u64>;
+pub type NiosPacket16x64 = NiosPacket<u16, u64>;
+pub type NiosPacket32x32 = NiosPacket<u32, u32>;
+
+/// Check the status flag of a response packet of any AxB format.
+pub fn nios_pkt_success(buf: &[u8]) -> bool {
+    buf.get(NIOS_PKT_IDX_FLAGS)
+        .is_some_and(|flags| (flags & NIOS_PKT_FLAG_SUCCESS) != 0)
+}
+
+#[derive(Clone)]
+pub struct NiosPacket<A, D> {
+    buf: [u8; NIOS_PKT_LEN],
+    format: PhantomData<(A, D)>,
+}
+
+impl<A, D> NiosPacket<A, D>
+where
+    A: NiosEncode + NiosDecode,
+    D: NiosEncode + NiosDecode,
+    (A, D): NiosPacketFormat,
+{
+    const IDX_DATA: usize = NIOS_PKT_IDX_ADDR + A::SIZE;
+
+    pub fn new() -> Self {
+        let mut buf = [0u8; NIOS_PKT_LEN];
+        buf[NIOS_PKT_IDX_MAGIC] = <(A, D)>::MAGIC;
+        Self {
+            buf,
+            format: PhantomData,
+        }
+    }
+
+    pub fn set(&mut self, target_id: u8, flags: u8, addr: A, data: D) {
+        self.buf = [0u8; NIOS_PKT_LEN];
+        self.set_magic(<(A, D)>::MAGIC);
+        self.set_target_id(target_id);
+        self.set_flags(flags);
+        self.set_addr(addr);
+        self.set_data(data);
+    }
+
+    /// Decode a packet from a received buffer. Fails if the buffer is shorter than
+    /// [`NIOS_PKT_LEN`]; bytes past the packet length are ignored.
+    pub fn from_vec(v: Vec<u8>) -> Result<Self> {
+        Self::from_bytes(&v)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let buf = bytes
+            .get(..NIOS_PKT_LEN)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "NIOS packet too short: {} bytes, expected {}",
+                    bytes.len(),
+                    NIOS_PKT_LEN
+                )
+            })?;
+        Ok(Self {
+            buf,
+            format: PhantomData,
+        })
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.buf.to_vec()
+    }
+
+    /// Encode the packet into `v`, reusing its allocation.
+    pub fn encode_into(&self, v: &mut Vec<u8>) {
+        v.clear();
+        v.extend_from_slice(&self.buf);
+    }
+
+    pub fn as_bytes(&self) -> &[u8; NIOS_PKT_LEN] {
+        &self.buf
+    }
+
+    pub fn success(&self) -> bool {
+        nios_pkt_success(&self.buf)
+    }
+
+    pub fn magic(&self) -> u8 {
+        self.buf[NIOS_PKT_IDX_MAGIC]
+    }
+    pub fn target_id(&self) -> u8 {
+        self.buf[NIOS_PKT_IDX_TARGET_ID]
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.buf[NIOS_PKT_IDX_FLAGS]
+    }
+
+    pub fn addr(&self) -> A {
+        A::decode(&self.buf[NIOS_PKT_IDX_ADDR..])
+    }
+    pub fn data(&self) -> D {
+        D::decode(&self.buf[Self::IDX_DATA..])
+    }
+
+    pub fn is_write(&self) -> bool {
+        (self.flags() & NIOS_PKT_FLAG_WRITE) != 0
+    }
+
+    pub fn set_magic(&mut self, magic: u8) -> &mut Self {
+        self.buf[NIOS_PKT_IDX_MAGIC] = magic;
+        self
+    }
+    pub fn set_target_id(&mut self, target_id: u8) -> &mut Self {
+        self.buf[NIOS_PKT_IDX_TARGET_ID] = target_id;
+        self
+    }
+    pub fn set_flag(&mut self, flag: u8) -> &mut Self {
+        self.buf[NIOS_PKT_IDX_FLAGS] |= flag;
+        self
+    }
+    pub fn set_flags(&mut self, flags: u8) -> &mut Self {
+        self.buf[NIOS_PKT_IDX_FLAGS] = flags;
+        self
+    }
+
+    pub fn set_addr(&mut self, addr: A) -> &mut Self {
+        addr.encode(&mut self.buf[NIOS_PKT_IDX_ADDR..]);
+        self
+    }
+    pub fn set_data(&mut self, data: D) -> &mut Self {
+        data.encode(&mut self.buf[Self::IDX_DATA..]);
+        self
+    }
+}
+
+impl<A, D> Default for NiosPacket<A, D>
+where
+    A: NiosEncode + NiosDecode,
+    D: NiosEncode + NiosDecode,
+    (A, D): NiosPacketFormat,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A, D> Debug for NiosPacket<A, D>
+where
+    A: NiosEncode + NiosDecode + Debug,
+    D: NiosEncode + NiosDecode + Debug,
+    (A, D): NiosPacketFormat,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NiosPacket")
+            .field("magic", &(self.magic() as char))
+            .field("target", &self.target_id())
+            .field("flags", &self.flags())
+            .field("addr", &self.addr())
+            .field("data", &self.data())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nios::constants::{NIOS_PKT_32X32_TARGET_WB_MSTR, NIOS_PKT_FLAG_READ};
+
+    #[test]
+    fn value_round_trip() {
+        let mut buf = [0u8; 8];
+
+        0x12u8.encode(&mut buf);
+        assert_eq!(buf[0], 0x12);
+        assert_eq!(u8::decode(&buf), 0x12);
+
+        0x1234u16.encode(&mut buf);
+        assert_eq!(buf[..2], [0x34, 0x12]);
+        assert_eq!(u16::decode(&buf), 0x1234);
+
+        0x1234_5678u32.encode(&mut buf);
+        assert_eq!(buf[..4], [0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(u32::decode(&buf), 0x1234_5678);
+
+        0x0123_4567_89ab_cdefu64.encode(&mut buf);
+        assert_eq!(buf, [0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01]);
+        assert_eq!(u64::decode(&buf), 0x0123_4567_89ab_cdef);
+    }
+
+    #[test]
+    fn packet_layout() {
+        let mut pkt = NiosPacket8x32::new();
+        pkt.set(0x02, NIOS_PKT_FLAG_WRITE, 0xab, 0x1122_3344);
+        assert_eq!(
+            pkt.as_bytes(),
+            &[
+                NIOS_PKT_8X32_MAGIC,
+                0x02,
+                NIOS_PKT_FLAG_WRITE,
+                0x00,
+                0xab,
+                0x44,
+                0x33,
+                0x22,
+                0x11,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0
+            ]
+        );
+
+        let mut pkt = NiosPacket16x64::new();
+        pkt.set(0x01, NIOS_PKT_FLAG_READ, 0xbeef, 0x0102_0304_0506_0708);
+        assert_eq!(pkt.magic(), NIOS_PKT_16X64_MAGIC);
+        assert_eq!(pkt.as_bytes()[4..6], [0xef, 0xbe]);
+        assert_eq!(
+            pkt.as_bytes()[6..14],
+            [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+        );
+        assert_eq!(pkt.as_bytes()[14..], [0, 0]);
+    }
+
+    #[test]
+    fn packet_round_trip() {
+        let mut pkt = NiosPacket32x32::new();
+        pkt.set(
+            NIOS_PKT_32X32_TARGET_WB_MSTR,
+            NIOS_PKT_FLAG_WRITE,
+            0xdead_beef,
+            0xcafe_f00d,
+        );
+        pkt.set_flag(NIOS_PKT_FLAG_SUCCESS);
+
+        let decoded = NiosPacket32x32::from_vec(pkt.clone().into_vec()).unwrap();
+        assert_eq!(decoded.as_bytes(), pkt.as_bytes());
+        assert_eq!(decoded.magic(), NIOS_PKT_32X32_MAGIC);
+        assert_eq!(decoded.target_id(), NIOS_PKT_32X32_TARGET_WB_MSTR);
+        assert_eq!(decoded.addr(), 0xdead_beef);
+        assert_eq!(decoded.data(), 0xcafe_f00d);
+        assert!(decoded.is_write());
+        assert!(decoded.success());
+
+        let mut v = vec![0xff; 3];
+        decoded.encode_into(&mut v);
+        assert_eq!(v, pkt.into_vec());
+    }
+
+    #[test]
+    fn from_bytes_rejects_short_responses() {
+        let pkt = NiosPacket8x8::new().into_vec();
+
+        assert!(NiosPacket8x8::from_bytes(&pkt[..NIOS_PKT_LEN - 1]).is_err());
+        assert!(NiosPacket8x8::from_bytes(&[]).is_err());
+        assert!(NiosPacket8x8::from_bytes(&pkt).is_ok());
+
+        /* Trailing bytes are ignored */
+        let mut long = pkt.clone();
+        long.extend_from_slice(&[0xaa; 4]);
+        assert_eq!(
+            NiosPacket8x8::from_vec(long).unwrap().as_bytes()[..],
+            pkt[..]
+        );
+    }
+
+    #[test]
+    fn success_flag() {
+        assert!(!nios_pkt_success(&[]));
+        assert!(!nios_pkt_success(&NiosPacket8x16::new().into_vec()));
+
+        let mut pkt = NiosPacket8x16::new();
+        pkt.set_flag(NIOS_PKT_FLAG_SUCCESS);
+        assert!(nios_pkt_success(pkt.as_bytes()));
+    }
+}